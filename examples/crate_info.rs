@@ -1,6 +1,7 @@
 use colored::*;
 use ratmole::error::Error;
 use ratmole::explore::CrateInfo;
+use ratmole::TargetSpec;
 use std::{env, io::Write};
 
 fn main() -> Result<(), Error> {
@@ -20,9 +21,29 @@ fn main() -> Result<(), Error> {
 
     let args: Vec<String> = env::args().collect();
     let crate_path = &args[1];
-    let crate_info = CrateInfo::new(&crate_path)?;
-    for item in crate_info.items() {
-        println!("{}", item);
+    let mut targets: Vec<_> = args[2..]
+        .iter()
+        .map(|triple| {
+            TargetSpec::builtin(triple).unwrap_or_else(|| {
+                let known: Vec<_> = TargetSpec::builtin_triples().collect();
+                panic!(
+                    "unknown target {}, expected one of {}",
+                    triple,
+                    known.join(", ")
+                )
+            })
+        })
+        .collect();
+    if targets.is_empty() {
+        targets.push(TargetSpec::default());
+    }
+    let crate_info = CrateInfo::with_targets(&crate_path, targets)?;
+    for (item, targets) in crate_info.items_with_targets() {
+        if targets.len() < crate_info.targets().len() {
+            println!("{} [{}]", item, targets.join(", "));
+        } else {
+            println!("{}", item);
+        }
     }
     Ok(())
 }
@@ -1,18 +1,22 @@
 use crate::{
-    cfg::{dev_cfg_expr, dev_platform_name, Platform},
+    cfg::Platform,
     error::{Error, Result},
+    lockfile::Lockfile,
+    patch::Patches,
+    target::TargetSpec,
+    workspace::Workspace,
 };
 use cargo::{
     core::{
-        dependency::DepKind, Dependency, FeatureMap, FeatureValue, Manifest, Package, Source,
-        SourceId,
+        dependency::DepKind, resolver::ResolveBehavior, Dependency, Edition, FeatureMap,
+        FeatureValue, Manifest, Package, PackageId, Shell, Source, SourceId,
     },
-    sources::{GitSource, PathSource, SourceConfigMap},
-    util::{interning::InternedString, toml::TomlManifest},
+    sources::{ReplacedSource, SourceConfigMap},
+    util::{homedir, interning::InternedString, toml::TomlManifest},
     Config,
 };
 use colored::*;
-use log::debug;
+use log::{debug, warn};
 use semver::Version;
 use std::{
     cmp::Ordering,
@@ -25,6 +29,19 @@ use std::{
     result::Result as StdResult,
 };
 
+// Read from the crate root, so that its .cargo/config.toml, and with
+// it any [source] replacement, applies. In offline mode only the index
+// and sources already in $CARGO_HOME are used, and nothing is fetched.
+pub fn cargo_config<T: AsRef<path::Path>>(crate_root: T, offline: bool) -> Result<Config> {
+    let cwd = crate_root.as_ref().to_path_buf();
+    let home = homedir(&cwd).ok_or(Error::HomeDirNotFound("cargo home dir not found"))?;
+    let mut config = Config::new(Shell::new(), cwd, home);
+    if offline {
+        config.configure(0, false, None, false, false, true, &None, &[], &[])?;
+    }
+    Ok(config)
+}
+
 pub fn parse_cargo<T: AsRef<path::Path>>(
     crate_root: T,
     config: &Config,
@@ -35,7 +52,13 @@ pub fn parse_cargo<T: AsRef<path::Path>>(
     let mut toml_content = String::new();
     toml_file.read_to_string(&mut toml_content)?;
 
-    let toml_manifest: TomlManifest = toml::from_str(&toml_content)?;
+    // Fields taken from the workspace have to be filled in first,
+    // as this version of Cargo does not know about them
+    let mut toml_value: toml::Value = toml::from_str(&toml_content)?;
+    if let Some(workspace) = Workspace::find(&crate_root)? {
+        workspace.inherit(&mut toml_value)?;
+    }
+    let toml_manifest: TomlManifest = toml_value.try_into()?;
     let toml_manifest = Rc::new(toml_manifest);
     let source_id = SourceId::for_path(crate_root.as_ref())?;
     let (manifest, paths) =
@@ -57,6 +80,8 @@ where
         .iter()
         .max_by_key(|x| x.version())
         .ok_or_else(|| Error::PackageNotFound(String::from(dep.name_in_toml().as_str())))?;
+    // Checks vendored sources against their .cargo-checksum.json
+    src.verify(latest.package_id())?;
     let pkg = Box::new(src).download_now(latest.package_id(), config)?;
     Ok(pkg)
 }
@@ -65,15 +90,247 @@ pub fn download_package_deps(pkg: &Package, config: &Config) -> Result<Vec<Packa
     download_dependencies(pkg.dependencies(), config)
 }
 
+// A directory all packages not given by path are taken from,
+// instead of their registry or git repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalSource {
+    // Made by `cargo vendor`
+    Vendor(PathBuf),
+    // Made by `cargo local-registry`
+    Registry(PathBuf),
+}
+
+impl LocalSource {
+    fn source_id(&self) -> Result<SourceId> {
+        let id = match self {
+            LocalSource::Vendor(path) => SourceId::for_directory(path)?,
+            LocalSource::Registry(path) => SourceId::for_local_registry(path)?,
+        };
+        Ok(id)
+    }
+}
+
+// Besides its requirement, what decides the
+// package a dependency is taken from
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SourceOverrides<'a> {
+    lockfile: Option<&'a Lockfile>,
+    local_source: Option<&'a LocalSource>,
+    patches: Option<&'a Patches>,
+}
+
+impl<'a> SourceOverrides<'a> {
+    pub fn new(
+        lockfile: Option<&'a Lockfile>,
+        local_source: Option<&'a LocalSource>,
+        patches: Option<&'a Patches>,
+    ) -> Self {
+        Self {
+            lockfile,
+            local_source,
+            patches,
+        }
+    }
+}
+
+// The features asked for on the root crate, like the
+// --features, --all-features and --no-default-features flags
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureSelection {
+    features: Vec<String>,
+    all_features: bool,
+    no_default_features: bool,
+}
+
+impl FeatureSelection {
+    // Features are written as on the command line,
+    // eg. `std`, `serde/derive` or `dep:serde`
+    pub fn new<I, S>(features: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            features: features.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn all_features(mut self) -> Self {
+        self.all_features = true;
+        self
+    }
+
+    pub fn no_default_features(mut self) -> Self {
+        self.no_default_features = true;
+        self
+    }
+
+    fn has_feature(package: &Package, feature: &str) -> bool {
+        match FeatureValue::new(InternedString::new(feature)) {
+            FeatureValue::Feature(name) => package.summary().features().contains_key(&name),
+            _ => true,
+        }
+    }
+
+    // With several root packages, as in a workspace, each feature
+    // has to be in one of them and is enabled on those which have it
+    pub(crate) fn check_any(&self, packages: &[&Package]) -> Result<()> {
+        let missing = self.features.iter().find(|feature| {
+            !packages
+                .iter()
+                .any(|package| Self::has_feature(package, feature))
+        });
+        match missing {
+            Some(feature) => {
+                let names: Vec<_> = packages
+                    .iter()
+                    .map(|package| package.name().as_str())
+                    .collect();
+                Err(Error::FeatureNotFound(names.join(", "), feature.clone()))
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn for_package(&self, package: &Package) -> Self {
+        Self {
+            features: self
+                .features
+                .iter()
+                .filter(|feature| Self::has_feature(package, feature))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    fn enabled_features(&self, package: &Package) -> Result<Vec<FeatureValue>> {
+        let feature_map = package.summary().features();
+        let mut enabled_features = Vec::new();
+        if self.all_features {
+            for feature in feature_map.keys() {
+                let feature = FeatureValue::Feature(*feature);
+                enabled_features.extend(transitive_features(&feature, feature_map));
+            }
+        } else if !self.no_default_features {
+            enabled_features.extend(default_features(package));
+        }
+        for feature in &self.features {
+            let feature = FeatureValue::new(InternedString::new(feature));
+            if let FeatureValue::Feature(name) = &feature {
+                if !feature_map.contains_key(name) {
+                    return Err(Error::FeatureNotFound(
+                        package.name().to_string(),
+                        feature.to_string(),
+                    ));
+                }
+            }
+            enabled_features.extend(transitive_features(&feature, feature_map));
+        }
+        let mut unique_features = Vec::new();
+        for feature in enabled_features {
+            if !unique_features.contains(&feature) {
+                unique_features.push(feature);
+            }
+        }
+        Ok(unique_features)
+    }
+}
+
+// Which of Cargo's feature resolvers decides the features of packages
+// used more than once. Both give one node per package version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolver {
+    // Features are unified across all uses
+    V1,
+    // Packages built for the host, ie. build dependencies and what they
+    // depend on, are kept apart from the normal ones
+    V2,
+}
+
+impl Resolver {
+    // `resolver = "2"` in the manifest, or the default of its edition
+    pub fn from_manifest(manifest: &Manifest) -> Self {
+        let behavior = manifest.resolve_behavior().unwrap_or_else(|| {
+            if manifest.edition() >= Edition::Edition2021 {
+                ResolveBehavior::V2
+            } else {
+                ResolveBehavior::V1
+            }
+        });
+        match behavior {
+            ResolveBehavior::V1 => Resolver::V1,
+            ResolveBehavior::V2 => Resolver::V2,
+        }
+    }
+}
+
+// The features a dependent asks for in its entry for a dependency,
+// including those it enables through its own features (`dep/feature`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureRequest {
+    dependent: PackageId,
+    features: Vec<InternedString>,
+    default_features: bool,
+}
+
+impl FeatureRequest {
+    pub fn dependent(&self) -> PackageId {
+        self.dependent
+    }
+
+    pub fn features(&self) -> &[InternedString] {
+        &self.features
+    }
+
+    pub fn uses_default_features(&self) -> bool {
+        self.default_features
+    }
+}
+
+// A dependent which refers to the package by another name,
+// with `alias = { package = "name", ... }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepRename {
+    dependent: PackageId,
+    name_in_toml: InternedString,
+}
+
+impl DepRename {
+    pub fn dependent(&self) -> PackageId {
+        self.dependent
+    }
+
+    pub fn name_in_toml(&self) -> InternedString {
+        self.name_in_toml
+    }
+
+    // The name the dependent's code uses for the crate
+    pub fn local_name(&self) -> String {
+        self.name_in_toml.replace('-', "_")
+    }
+}
+
 #[derive(Debug, Clone, Eq)]
 pub struct DependentPackage {
     package: Package,
     enabled_features: Vec<FeatureValue>,
+    // Built for the host, as a build dependency or a dependency of one,
+    // separately from normal uses. Only with the v2 resolver.
+    host: bool,
+    // Triples of the targets the package is a dependency on.
+    // Not part of the identity of the package.
+    targets: Vec<String>,
+    requested_features: Vec<FeatureRequest>,
+    renames: Vec<DepRename>,
+    // Taken from a [patch] or [replace] rather than its own source
+    patched: bool,
 }
 
 impl Ord for DependentPackage {
     fn cmp(&self, ot: &Self) -> Ordering {
-        self.package.cmp(&ot.package)
+        self.package.cmp(&ot.package).then(self.host.cmp(&ot.host))
     }
 }
 
@@ -83,27 +340,48 @@ impl PartialOrd for DependentPackage {
     }
 }
 
+// Features are unified, so there is only one node per package
+// (and per host/target split with the v2 resolver)
 impl Hash for DependentPackage {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.package.hash(state);
-        for feat in &self.enabled_features {
-            feat.hash(state);
-        }
+        self.host.hash(state);
     }
 }
 
 impl PartialEq for DependentPackage {
     fn eq(&self, rhs: &DependentPackage) -> bool {
-        self.package == rhs.package && self.enabled_features == rhs.enabled_features
+        self.package == rhs.package && self.host == rhs.host
     }
 }
 
 impl DependentPackage {
-    fn from_cargo(pkg: Package, pkg_parent: &Self, pkg_dep: &Dependency) -> Self {
+    fn from_cargo(
+        pkg: Package,
+        pkg_parent: &Self,
+        pkg_dep: &Dependency,
+        targets: Vec<String>,
+    ) -> Self {
         let name = pkg_dep.name_in_toml();
         let feature_map = pkg.summary().features();
-        let features_from_dep: Vec<_> = pkg_dep
-            .features()
+        let mut requested: Vec<_> = pkg_dep.features().to_vec();
+        requested.extend(
+            pkg_parent
+                .enabled_features
+                .iter()
+                .filter_map(|feat| match feat {
+                    FeatureValue::DepFeature {
+                        dep_name,
+                        dep_feature,
+                        weak: _,
+                    } if dep_name == &name => Some(*dep_feature),
+                    _ => None,
+                }),
+        );
+        requested.sort();
+        requested.dedup();
+
+        let mut enabled_features: Vec<_> = requested
             .iter()
             .map(|feat_name| {
                 let feature = FeatureValue::Feature(*feat_name);
@@ -111,36 +389,33 @@ impl DependentPackage {
             })
             .flatten()
             .collect();
-        let features_from_parent = pkg_parent
-            .enabled_features
-            .iter()
-            .filter_map(|feat| match feat {
-                FeatureValue::DepFeature {
-                    dep_name,
-                    dep_feature,
-                    weak: _,
-                } => {
-                    if dep_name == &name {
-                        let feature = FeatureValue::Feature(*dep_feature);
-                        Some(transitive_features(&feature, feature_map))
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            })
-            .flatten();
-
-        let mut enabled_features = features_from_dep;
-        enabled_features.extend(features_from_parent);
         if pkg_dep.uses_default_features() {
             let default_features = default_features(&pkg);
             enabled_features.extend(default_features.into_iter());
         }
 
+        let request = FeatureRequest {
+            dependent: pkg_parent.package.package_id(),
+            features: requested,
+            default_features: pkg_dep.uses_default_features(),
+        };
+        let mut renames = Vec::new();
+        if name != pkg_dep.package_name() {
+            renames.push(DepRename {
+                dependent: pkg_parent.package.package_id(),
+                name_in_toml: name,
+            });
+        }
+        // Proc-macros are built for the host, like build scripts
+        let host = pkg_parent.host || pkg_dep.kind() == DepKind::Build || pkg.proc_macro();
         Self {
             package: pkg,
             enabled_features,
+            host,
+            targets,
+            requested_features: vec![request],
+            renames,
+            patched: false,
         }
     }
 
@@ -149,15 +424,83 @@ impl DependentPackage {
         Self {
             package: pkg,
             enabled_features,
+            host: false,
+            targets: Vec::new(),
+            requested_features: Vec::new(),
+            renames: Vec::new(),
+            patched: false,
         }
     }
 
-    fn dependencies(&self) -> Vec<&Dependency> {
+    pub fn from_cargo_with_features(pkg: Package, features: &FeatureSelection) -> Result<Self> {
+        let enabled_features = features.enabled_features(&pkg)?;
+        Ok(Self {
+            package: pkg,
+            enabled_features,
+            host: false,
+            targets: Vec::new(),
+            requested_features: Vec::new(),
+            renames: Vec::new(),
+            patched: false,
+        })
+    }
+
+    // Merges in what another use of the same package asks for: features,
+    // targets and who requested them. Returns whether the package now has
+    // features or targets it did not have before, in which case its own
+    // dependencies need to be worked out again.
+    pub(crate) fn unify(&mut self, other: &Self) -> bool {
+        let mut grown = false;
+        for feature in &other.enabled_features {
+            if !self.enabled_features.contains(feature) {
+                self.enabled_features.push(feature.clone());
+                grown = true;
+            }
+        }
+        for target in &other.targets {
+            if !self.targets.contains(target) {
+                self.targets.push(target.clone());
+                grown = true;
+            }
+        }
+        for request in &other.requested_features {
+            match self
+                .requested_features
+                .iter_mut()
+                .find(|req| req.dependent == request.dependent)
+            {
+                Some(req) => {
+                    for feature in &request.features {
+                        if !req.features.contains(feature) {
+                            req.features.push(*feature);
+                        }
+                    }
+                    req.default_features |= request.default_features;
+                }
+                None => self.requested_features.push(request.clone()),
+            }
+        }
+        for rename in &other.renames {
+            if !self.renames.contains(rename) {
+                self.renames.push(rename.clone());
+            }
+        }
+        self.patched |= other.patched;
+        grown
+    }
+
+    // Those of the given kinds, along with the
+    // triples of the targets each one is active on
+    fn dependencies(
+        &self,
+        targets: &[TargetSpec],
+        kinds: &[DepKind],
+    ) -> Vec<(&Dependency, Vec<String>)> {
         self.package
             .dependencies()
             .iter()
             .filter(|dep| {
-                if dep.kind() == DepKind::Normal {
+                if kinds.contains(&dep.kind()) {
                     if !dep.is_optional() {
                         true
                     } else {
@@ -174,39 +517,57 @@ impl DependentPackage {
                     false
                 }
             })
-            .filter(|dep| {
-                if let Some(platform) = dep.platform() {
-                    let platform: Platform = platform.clone().into();
-                    // FIXME: Generalize this to all platforms
-                    match platform {
-                        Platform::Name(name) => dev_platform_name() == name,
-                        Platform::CfgExpr(cfg_expr) => cfg_expr.is_satisfied_by(&dev_cfg_expr()),
-                    }
+            .filter_map(|dep| {
+                let platform: Option<Platform> = dep.platform().cloned().map(Platform::from);
+                let active_on: Vec<_> = targets
+                    .iter()
+                    .filter(|target| self.is_active_on(target.triple()))
+                    .filter(|target| {
+                        platform
+                            .as_ref()
+                            .map_or(true, |platform| target.matches_platform(platform))
+                    })
+                    .map(|target| String::from(target.triple()))
+                    .collect();
+                if active_on.is_empty() {
+                    None
                 } else {
-                    true
+                    Some((dep, active_on))
                 }
             })
             .collect()
     }
 
+    // crates_io is from crates_io_source, and shared between packages
     pub fn download_dependencies(
         &self,
         config: &Config,
-        update_crates_io: bool,
-    ) -> Result<Vec<Self>> {
+        crates_io: &mut dyn Source,
+        targets: &[TargetSpec],
+        kinds: &[DepKind],
+        overrides: SourceOverrides<'_>,
+    ) -> Result<Vec<(DepKind, Self)>> {
         let _lock = config.acquire_package_cache_lock()?;
         let crates_io_id = SourceId::crates_io(config)?;
-        let config_map = SourceConfigMap::new(config)?;
-        let mut crates_io = config_map.load(crates_io_id, &Default::default())?;
-        if update_crates_io {
-            crates_io.update()?;
-        }
 
         let mut dep_pkgs = Vec::new();
-        for dep in self.dependencies() {
-            let pkg = download_dependency(dep, config, &crates_io_id, crates_io.as_mut())?;
-            let dep_pkg = Self::from_cargo(pkg, self, dep);
-            dep_pkgs.push(dep_pkg);
+        for (dep, active_on) in self.dependencies(targets, kinds) {
+            let patched = match overrides.patches {
+                Some(patches) => {
+                    patch_dependency(dep, patches, config, &crates_io_id, crates_io, overrides)?
+                }
+                None => None,
+            };
+            let (pkg, is_patched) = match patched {
+                Some(pkg) => (pkg, true),
+                None => (
+                    download_dependency(dep, config, &crates_io_id, crates_io, overrides)?,
+                    false,
+                ),
+            };
+            let mut dep_pkg = Self::from_cargo(pkg, self, dep, active_on);
+            dep_pkg.patched = is_patched;
+            dep_pkgs.push((dep.kind(), dep_pkg));
         }
         Ok(dep_pkgs)
     }
@@ -222,6 +583,69 @@ impl DependentPackage {
     pub fn package(&self) -> &Package {
         &self.package
     }
+
+    // Transitively closed, so `default` comes with what it enables
+    pub fn enabled_features(&self) -> &[FeatureValue] {
+        &self.enabled_features
+    }
+
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.host
+    }
+
+    // With the version 2 resolver, a package may be built both for the
+    // host and for the target, with different features. The two builds
+    // are kept apart by this.
+    pub(crate) fn key(&self) -> (PackageId, bool) {
+        (self.package.package_id(), self.host)
+    }
+
+    // One entry per dependent, after unification
+    pub fn requested_features(&self) -> &[FeatureRequest] {
+        &self.requested_features
+    }
+
+    pub fn is_patched(&self) -> bool {
+        self.patched
+    }
+
+    pub fn renames(&self) -> &[DepRename] {
+        &self.renames
+    }
+
+    // The name the code of dependent uses for this package's library,
+    // if it does not use the library's own name
+    pub fn renamed_in(&self, dependent: PackageId) -> Option<String> {
+        self.renames
+            .iter()
+            .find(|rename| rename.dependent == dependent)
+            .map(DepRename::local_name)
+    }
+
+    // The name its items are rooted at
+    pub fn lib_crate_name(&self) -> Option<String> {
+        self.package
+            .targets()
+            .iter()
+            .find(|target| target.is_lib())
+            .map(|target| target.crate_name())
+    }
+
+    pub fn is_active_on(&self, triple: &str) -> bool {
+        self.targets.iter().any(|target| target == triple)
+    }
+
+    pub(crate) fn set_targets(&mut self, targets: Vec<String>) {
+        self.targets = targets;
+    }
+
+    pub(crate) fn set_host(&mut self, host: bool) {
+        self.host = host;
+    }
 }
 
 impl From<DependentPackage> for Package {
@@ -262,12 +686,26 @@ fn transitive_features(feature: &FeatureValue, feature_map: &FeatureMap) -> Vec<
     features
 }
 
+// crates.io, or what replaces it in the config, updated once for all the
+// packages of a graph. Offline, cargo does not fetch the index, but still
+// reads sources which replace crates.io, like vendor directories.
+pub(crate) fn crates_io_source(config: &Config) -> Result<Box<dyn Source + '_>> {
+    let _lock = config.acquire_package_cache_lock()?;
+    let config_map = SourceConfigMap::new(config)?;
+    let mut crates_io = config_map.load(SourceId::crates_io(config)?, &Default::default())?;
+    crates_io.update()?;
+    Ok(crates_io)
+}
+
 pub fn download_dependencies(dependencies: &[Dependency], config: &Config) -> Result<Vec<Package>> {
     let _lock = config.acquire_package_cache_lock()?;
     let crates_io_id = SourceId::crates_io(config)?;
     let config_map = SourceConfigMap::new(config)?;
     let mut crates_io = config_map.load(crates_io_id, &Default::default())?;
-    crates_io.update()?;
+    // Offline, the index is what was fetched earlier
+    if !config.offline() {
+        crates_io.update()?;
+    }
 
     let mut dep_pkgs = Vec::new();
     for dep in dependencies {
@@ -279,21 +717,93 @@ pub fn download_dependencies(dependencies: &[Dependency], config: &Config) -> Re
             config,
             &crates_io_id,
             crates_io.as_mut(),
+            SourceOverrides::default(),
         )?);
         debug!(" ... downloaded {}", dep.name_in_toml());
     }
     Ok(dep_pkgs)
 }
 
+// The package from the first [replace] or [patch] which applies to dep
+fn patch_dependency(
+    dep: &Dependency,
+    patches: &Patches,
+    config: &Config,
+    crates_io_id: &SourceId,
+    crates_io: &mut dyn Source,
+    overrides: SourceOverrides<'_>,
+) -> Result<Option<Package>> {
+    let overrides = SourceOverrides {
+        patches: None,
+        ..overrides
+    };
+    for (patch, check_version) in patches.candidates(dep) {
+        debug!("Trying {} from {}", dep.package_name(), patch.source_id());
+        let pkg = download_dependency(patch, config, crates_io_id, crates_io, overrides)?;
+        if !check_version || dep.version_req().matches(pkg.version()) {
+            return Ok(Some(pkg));
+        }
+        // Like Cargo, which leaves such a patch unused
+        warn!(
+            "Patch {} v{} from {} is unused, as {} is required",
+            pkg.name(),
+            pkg.version(),
+            patch.source_id(),
+            dep.version_req()
+        );
+    }
+    Ok(None)
+}
+
 fn download_dependency(
     dep: &Dependency,
     config: &Config,
     crates_io_id: &SourceId,
     crates_io: &mut dyn Source,
+    overrides: SourceOverrides<'_>,
+) -> Result<Package> {
+    let result = download_dependency_online(dep, config, crates_io_id, crates_io, overrides);
+    if !config.offline() {
+        return result;
+    }
+    // Offline, failing to find or download a package is
+    // down to it not having been fetched earlier
+    result.map_err(|err| match err {
+        Error::PackageNotFound(_) => Error::NotCached(
+            dep.package_name().to_string(),
+            format!("no version matching {}", dep.version_req()),
+        ),
+        Error::Anyhow(err) => Error::NotCached(dep.package_name().to_string(), err.to_string()),
+        err => err,
+    })
+}
+
+fn download_dependency_online(
+    dep: &Dependency,
+    config: &Config,
+    crates_io_id: &SourceId,
+    crates_io: &mut dyn Source,
+    overrides: SourceOverrides<'_>,
 ) -> Result<Package> {
     debug!("Downloading {} ...", dep.name_in_toml());
+    let pinned;
+    let dep = match overrides.lockfile {
+        Some(lockfile) => {
+            pinned = lockfile.pin(dep)?;
+            &pinned
+        }
+        None => dep,
+    };
     let dep_src_id = dep.source_id();
-    if &dep_src_id == crates_io_id {
+    if let (Some(local_source), false) = (overrides.local_source, dep_src_id.is_path()) {
+        debug!("from local source");
+        let local_id = local_source.source_id()?;
+        let config_map = SourceConfigMap::empty(config)?;
+        let src = config_map.load(local_id, &Default::default())?;
+        let mut src = ReplacedSource::new(dep_src_id, local_id, src);
+        src.update()?;
+        download_dependency_from_src(dep, src, config)
+    } else if &dep_src_id == crates_io_id {
         debug!("from crates");
         download_dependency_from_src(dep, crates_io, config)
     } else if dep_src_id.is_path() {
@@ -302,13 +812,12 @@ fn download_dependency(
             .url()
             .to_file_path()
             .unwrap_or_else(|_| panic!("path of {} must be valid", dep.name_in_toml()));
-        let mut src = PathSource::new(&path, dep_src_id, config);
-        src.update()?;
-        download_dependency_from_src(dep, &mut src, config)
-    } else if dep_src_id.is_git() {
-        debug!("from git");
-        download_dependency_from_src(dep, GitSource::new(dep_src_id, config)?, config)
+        // Read like the root crate, since it may be a workspace member
+        let (manifest, manifest_path) = parse_cargo(&path, config)?;
+        Ok(Package::new(manifest, &manifest_path))
     } else {
+        // Git repositories and other registries. These are loaded through
+        // the config too, in case they are replaced by a vendor directory.
         debug!("from elsewhere");
         let config_map = SourceConfigMap::new(config)?;
         let mut src = config_map.load(dep_src_id, &Default::default())?;
@@ -316,3 +825,76 @@ fn download_dependency(
         download_dependency_from_src(dep, &mut src, config)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::fs;
+
+    // name v0.1.0, with the rest of its manifest in manifest,
+    // written to a temporary directory
+    pub(crate) fn package(name: &str, manifest: &str) -> Package {
+        let root = std::env::temp_dir().join(format!("ratmole-{}-{}", std::process::id(), name));
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n{}",
+                name, manifest
+            ),
+        )
+        .unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        let config = cargo_config(&root, true).unwrap();
+        let (manifest, manifest_path) = parse_cargo(&root, &config).unwrap();
+        Package::new(manifest, &manifest_path)
+    }
+
+    fn request(dependent: PackageId, features: &[&str], default_features: bool) -> FeatureRequest {
+        FeatureRequest {
+            dependent,
+            features: features.iter().copied().map(InternedString::new).collect(),
+            default_features,
+        }
+    }
+
+    #[test]
+    fn test_unify() {
+        let pkg = package("foo", "[features]\na = []\nb = []\n");
+        let source_id = pkg.package_id().source_id();
+        let bar = PackageId::new("bar", "1.0.0", source_id).unwrap();
+        let baz = PackageId::new("baz", "1.0.0", source_id).unwrap();
+        let feature = |name: &str| FeatureValue::Feature(InternedString::new(name));
+        let dependent = |features: &[&str], target: &str, requests| DependentPackage {
+            package: pkg.clone(),
+            enabled_features: features.iter().map(|name| feature(name)).collect(),
+            host: false,
+            targets: vec![String::from(target)],
+            requested_features: requests,
+            renames: Vec::new(),
+            patched: false,
+        };
+        let linux = "x86_64-unknown-linux-gnu";
+
+        let mut unified = dependent(&["a"], linux, vec![request(bar, &["a"], false)]);
+        let other = dependent(
+            &["a", "b"],
+            linux,
+            vec![request(bar, &["b"], true), request(baz, &["a"], false)],
+        );
+        assert!(unified.unify(&other));
+        assert_eq!(unified.enabled_features, [feature("a"), feature("b")]);
+        assert_eq!(unified.targets, [linux]);
+        assert_eq!(
+            unified.requested_features,
+            [request(bar, &["a", "b"], true), request(baz, &["a"], false)]
+        );
+        // Nothing more to build
+        assert!(!unified.unify(&other));
+
+        let wasm = "wasm32-unknown-unknown";
+        assert!(unified.unify(&dependent(&[], wasm, Vec::new())));
+        assert_eq!(unified.targets, [linux, wasm]);
+        assert_eq!(unified.enabled_features.len(), 2);
+    }
+}
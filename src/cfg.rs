@@ -1,5 +1,16 @@
+use crate::error::{Error, Result};
 use cargo_platform::{Cfg as CargoCfg, CfgExpr as CargoCfgExpr, Platform as CargoPlatform};
+use log::warn;
+use proc_macro2::{TokenStream, TokenTree};
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    iter::{self, FromIterator},
+    str::FromStr,
+};
+use syn::{parse::ParseStream, punctuated::Punctuated, Attribute, Lit, Meta, NestedMeta, Token};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CfgExpr {
     Not(Box<CfgExpr>),
     All(Vec<CfgExpr>),
@@ -7,12 +18,19 @@ pub enum CfgExpr {
     Value(Cfg),
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Cfg {
     Value(String),
     KeyValue(String, String),
 }
 
+// The cfg options which are set, eg. those of a target
+// along with the enabled features
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfgSet {
+    cfgs: HashSet<Cfg>,
+}
+
 pub enum Platform {
     Name(String),
     CfgExpr(CfgExpr),
@@ -69,41 +87,493 @@ impl From<CargoPlatform> for Platform {
     }
 }
 
+impl Display for Cfg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Cfg::Value(name) => write!(f, "{}", name),
+            Cfg::KeyValue(key, value) => write!(f, "{} = {:?}", key, value),
+        }
+    }
+}
+
+impl Display for CfgExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn join(exprs: &[CfgExpr]) -> String {
+            let exprs: Vec<_> = exprs.iter().map(CfgExpr::to_string).collect();
+            exprs.join(", ")
+        }
+
+        match self {
+            CfgExpr::Not(e) => write!(f, "not({})", e),
+            CfgExpr::All(e) => write!(f, "all({})", join(e)),
+            CfgExpr::Any(e) => write!(f, "any({})", join(e)),
+            CfgExpr::Value(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// Parses what goes inside #[cfg(...)], eg. `all(unix, target_env = "gnu")`
+impl FromStr for CfgExpr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        syn::parse_str(s)
+            .ok()
+            .and_then(|meta| CfgExpr::from_meta(&meta))
+            .ok_or_else(|| Error::InvalidCfg(format!("invalid cfg expression: {}", s)))
+    }
+}
+
+// Parses a single option, eg. `unix` or `target_os = "linux"`
+impl FromStr for Cfg {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.parse()? {
+            CfgExpr::Value(cfg) => Ok(cfg),
+            _ => Err(Error::InvalidCfg(format!("invalid cfg: {}", s))),
+        }
+    }
+}
+
+impl CfgSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, cfg: Cfg) -> bool {
+        self.cfgs.insert(cfg)
+    }
+
+    pub fn contains(&self, cfg: &Cfg) -> bool {
+        self.cfgs.contains(cfg)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cfg> {
+        self.cfgs.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cfgs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cfgs.is_empty()
+    }
+}
+
+impl FromIterator<Cfg> for CfgSet {
+    fn from_iter<I: IntoIterator<Item = Cfg>>(iter: I) -> Self {
+        Self {
+            cfgs: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<Cfg> for CfgSet {
+    fn extend<I: IntoIterator<Item = Cfg>>(&mut self, iter: I) {
+        self.cfgs.extend(iter)
+    }
+}
+
 impl CfgExpr {
-    fn is_satisfied_by_slice(&self, cfg: &[&Cfg]) -> bool {
+    // Parses the predicate of a #[cfg(...)] or #[cfg_attr(...)]
+    pub(crate) fn from_meta(meta: &NestedMeta) -> Option<Self> {
+        let meta = match meta {
+            NestedMeta::Meta(meta) => meta,
+            NestedMeta::Lit(_) => return None,
+        };
+        match meta {
+            Meta::Path(path) => Some(CfgExpr::Value(Cfg::Value(path.get_ident()?.to_string()))),
+            Meta::NameValue(nv) => match &nv.lit {
+                Lit::Str(value) => Some(CfgExpr::Value(Cfg::KeyValue(
+                    nv.path.get_ident()?.to_string(),
+                    value.value(),
+                ))),
+                _ => None,
+            },
+            Meta::List(list) => {
+                let mut exprs = list
+                    .nested
+                    .iter()
+                    .map(CfgExpr::from_meta)
+                    .collect::<Option<Vec<_>>>()?;
+                let name = list.path.get_ident()?;
+                if name == "all" {
+                    Some(CfgExpr::All(exprs))
+                } else if name == "any" {
+                    Some(CfgExpr::Any(exprs))
+                } else if name == "not" && exprs.len() == 1 {
+                    Some(CfgExpr::Not(Box::new(exprs.pop().unwrap())))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    // Whether the predicate holds when exactly the cfgs in the set are on
+    pub fn eval(&self, cfgs: &CfgSet) -> bool {
         match self {
-            CfgExpr::Not(e) => !e.is_satisfied_by_slice(cfg),
-            CfgExpr::All(e) => e.iter().all(|x| x.is_satisfied_by_slice(cfg)),
-            CfgExpr::Any(e) => e.iter().any(|x| x.is_satisfied_by_slice(cfg)),
-            CfgExpr::Value(e) => cfg.contains(&e),
+            CfgExpr::Not(e) => !e.eval(cfgs),
+            CfgExpr::All(e) => e.iter().all(|x| x.eval(cfgs)),
+            CfgExpr::Any(e) => e.iter().any(|x| x.eval(cfgs)),
+            CfgExpr::Value(e) => cfgs.contains(e),
         }
     }
 
-    pub fn is_satisfied_by(&self, ot: &Self) -> bool {
-        match ot {
-            CfgExpr::Not(e) => !self.is_satisfied_by(e),
-            CfgExpr::All(e) => e.iter().all(|x| self.is_satisfied_by(x)),
-            CfgExpr::Any(e) => e.iter().any(|x| self.is_satisfied_by(x)),
-            CfgExpr::Value(e) => self.is_satisfied_by_slice(&[e]),
+    // all() holds everywhere and any() nowhere
+    pub fn is_always_true(&self) -> bool {
+        matches!(self, CfgExpr::All(e) if e.is_empty())
+    }
+
+    pub fn is_always_false(&self) -> bool {
+        matches!(self, CfgExpr::Any(e) if e.is_empty())
+    }
+
+    // An equivalent expression which is easier to read: nested all/any are
+    // flattened, double negations removed and duplicate operands dropped.
+    // Expressions found to be constant become all() or any().
+    pub fn simplify(&self) -> CfgExpr {
+        match self {
+            CfgExpr::Value(_) => self.clone(),
+            CfgExpr::Not(e) => match e.simplify() {
+                CfgExpr::Not(e) => *e,
+                e if e.is_always_true() => CfgExpr::Any(Vec::new()),
+                e if e.is_always_false() => CfgExpr::All(Vec::new()),
+                e => CfgExpr::Not(Box::new(e)),
+            },
+            CfgExpr::All(e) => simplify_operands(e, true),
+            CfgExpr::Any(e) => simplify_operands(e, false),
         }
     }
+
+    fn negated(&self) -> CfgExpr {
+        match self {
+            CfgExpr::Not(e) => (**e).clone(),
+            e => CfgExpr::Not(Box::new(e.clone())),
+        }
+    }
+}
+
+// In all(), operands which are always true can be dropped and one which
+// is always false decides the result. For any() it is the other way round.
+fn simplify_operands(exprs: &[CfgExpr], is_all: bool) -> CfgExpr {
+    let (identity, absorbing) = if is_all {
+        (CfgExpr::All(Vec::new()), CfgExpr::Any(Vec::new()))
+    } else {
+        (CfgExpr::Any(Vec::new()), CfgExpr::All(Vec::new()))
+    };
+    let mut operands: Vec<CfgExpr> = Vec::new();
+    for expr in exprs.iter().map(CfgExpr::simplify) {
+        let flattened = match expr {
+            CfgExpr::All(e) if is_all => e,
+            CfgExpr::Any(e) if !is_all => e,
+            e => vec![e],
+        };
+        for e in flattened {
+            // x along with not(x) is absorbing too
+            if e == absorbing || operands.contains(&e.negated()) {
+                return absorbing;
+            }
+            if e != identity && !operands.contains(&e) {
+                operands.push(e);
+            }
+        }
+    }
+    if operands.len() == 1 {
+        operands.pop().unwrap()
+    } else if is_all {
+        CfgExpr::All(operands)
+    } else {
+        CfgExpr::Any(operands)
+    }
+}
+
+// An item is active unless one of its #[cfg(...)] predicates is false.
+// Predicates we fail to parse are assumed to hold.
+pub(crate) fn is_active(attrs: &[syn::Attribute], cfgs: &CfgSet) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) if list.nested.len() == 1 => CfgExpr::from_meta(&list.nested[0]),
+            _ => None,
+        })
+        .all(|expr| expr.eval(cfgs))
+}
+
+// #[cfg_attr(pred, a, b(c))] is replaced by #[a] #[b(c)] if pred holds
+// and dropped otherwise. The attributes it expands to may be cfg_attrs too.
+fn expand_cfg_attrs(attrs: &mut Vec<Attribute>, cfgs: &CfgSet) {
+    let mut pending = std::mem::take(attrs);
+    pending.reverse();
+    while let Some(attr) = pending.pop() {
+        if !attr.path.is_ident("cfg_attr") {
+            attrs.push(attr);
+            continue;
+        }
+        match attr.parse_args_with(parse_cfg_attr) {
+            Ok((pred, expanded)) => {
+                if pred.eval(cfgs) {
+                    pending.extend(expanded.into_iter().rev().map(|(path, tokens)| Attribute {
+                        pound_token: attr.pound_token,
+                        style: attr.style,
+                        bracket_token: attr.bracket_token,
+                        path,
+                        tokens,
+                    }));
+                }
+            }
+            Err(err) => {
+                warn!("failed to expand cfg_attr: {}", err);
+                attrs.push(attr);
+            }
+        }
+    }
+}
+
+fn parse_cfg_attr(input: ParseStream) -> syn::Result<(CfgExpr, Vec<(syn::Path, TokenStream)>)> {
+    let pred: NestedMeta = input.parse()?;
+    let pred = CfgExpr::from_meta(&pred).ok_or_else(|| input.error("unsupported predicate"))?;
+    input.parse::<Token![,]>()?;
+    let mut attrs = Vec::new();
+    while !input.is_empty() {
+        let path = input.call(syn::Path::parse_mod_style)?;
+        // Whatever follows the path up to the next comma, eg. `= "foo.rs"` or `(Debug)`
+        let tokens = input.step(|cursor| {
+            let mut rest = *cursor;
+            let mut tokens = TokenStream::new();
+            while let Some((tt, next)) = rest.token_tree() {
+                match &tt {
+                    TokenTree::Punct(punct) if punct.as_char() == ',' => break,
+                    _ => {
+                        tokens.extend(iter::once(tt));
+                        rest = next;
+                    }
+                }
+            }
+            Ok((tokens, rest))
+        })?;
+        attrs.push((path, tokens));
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok((pred, attrs))
+}
+
+// Expands the cfg_attrs, then says whether the cfgs leave the owner in
+fn configure_attrs(attrs: &mut Vec<Attribute>, cfgs: &CfgSet) -> bool {
+    expand_cfg_attrs(attrs, cfgs);
+    is_active(attrs, cfgs)
+}
+
+fn configure_vec<T>(
+    things: &mut Vec<T>,
+    attrs_of: fn(&mut T) -> Option<&mut Vec<Attribute>>,
+    cfgs: &CfgSet,
+) {
+    let all = std::mem::take(things);
+    things.extend(all.into_iter().filter_map(|mut thing| {
+        let active = attrs_of(&mut thing).map_or(true, |attrs| configure_attrs(attrs, cfgs));
+        if active {
+            Some(thing)
+        } else {
+            None
+        }
+    }));
+}
+
+fn configure_punctuated<T, P: Default>(
+    things: &mut Punctuated<T, P>,
+    attrs_of: fn(&mut T) -> &mut Vec<Attribute>,
+    cfgs: &CfgSet,
+) {
+    let all = std::mem::take(things);
+    *things = all
+        .into_iter()
+        .filter_map(|mut thing| {
+            if configure_attrs(attrs_of(&mut thing), cfgs) {
+                Some(thing)
+            } else {
+                None
+            }
+        })
+        .collect();
+}
+
+fn item_attrs(item: &mut syn::Item) -> Option<&mut Vec<Attribute>> {
+    use syn::Item;
+    match item {
+        Item::Const(item) => Some(&mut item.attrs),
+        Item::Enum(item) => Some(&mut item.attrs),
+        Item::ExternCrate(item) => Some(&mut item.attrs),
+        Item::Fn(item) => Some(&mut item.attrs),
+        Item::ForeignMod(item) => Some(&mut item.attrs),
+        Item::Impl(item) => Some(&mut item.attrs),
+        Item::Macro(item) => Some(&mut item.attrs),
+        Item::Macro2(item) => Some(&mut item.attrs),
+        Item::Mod(item) => Some(&mut item.attrs),
+        Item::Static(item) => Some(&mut item.attrs),
+        Item::Struct(item) => Some(&mut item.attrs),
+        Item::Trait(item) => Some(&mut item.attrs),
+        Item::TraitAlias(item) => Some(&mut item.attrs),
+        Item::Type(item) => Some(&mut item.attrs),
+        Item::Union(item) => Some(&mut item.attrs),
+        Item::Use(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+fn impl_item_attrs(item: &mut syn::ImplItem) -> Option<&mut Vec<Attribute>> {
+    use syn::ImplItem;
+    match item {
+        ImplItem::Const(item) => Some(&mut item.attrs),
+        ImplItem::Method(item) => Some(&mut item.attrs),
+        ImplItem::Type(item) => Some(&mut item.attrs),
+        ImplItem::Macro(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+fn trait_item_attrs(item: &mut syn::TraitItem) -> Option<&mut Vec<Attribute>> {
+    use syn::TraitItem;
+    match item {
+        TraitItem::Const(item) => Some(&mut item.attrs),
+        TraitItem::Method(item) => Some(&mut item.attrs),
+        TraitItem::Type(item) => Some(&mut item.attrs),
+        TraitItem::Macro(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+fn foreign_item_attrs(item: &mut syn::ForeignItem) -> Option<&mut Vec<Attribute>> {
+    use syn::ForeignItem;
+    match item {
+        ForeignItem::Fn(item) => Some(&mut item.attrs),
+        ForeignItem::Static(item) => Some(&mut item.attrs),
+        ForeignItem::Type(item) => Some(&mut item.attrs),
+        ForeignItem::Macro(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+fn configure_fields(fields: &mut syn::Fields, cfgs: &CfgSet) {
+    match fields {
+        syn::Fields::Named(named) => configure_punctuated(&mut named.named, |f| &mut f.attrs, cfgs),
+        syn::Fields::Unnamed(unnamed) => {
+            configure_punctuated(&mut unnamed.unnamed, |f| &mut f.attrs, cfgs)
+        }
+        syn::Fields::Unit => {}
+    }
 }
 
-pub(crate) fn dev_cfg_expr() -> CfgExpr {
-    CfgExpr::All(vec![
-        CfgExpr::Value(Cfg::from(("target_arch", "x86_64"))),
-        CfgExpr::Value(Cfg::from(("target_feature", "fxsr"))),
-        CfgExpr::Value(Cfg::from(("target_feature", "sse"))),
-        CfgExpr::Value(Cfg::from(("target_feature", "sse2"))),
-        CfgExpr::Value(Cfg::from(("target_os", "linux"))),
-        CfgExpr::Value(Cfg::from(("target_family", "unix"))),
-        CfgExpr::Value(Cfg::from(("target_env", "gnu"))),
-        CfgExpr::Value(Cfg::from(("target_endian", "little"))),
-        CfgExpr::Value(Cfg::from(("target_pointer_width", "64"))),
-        CfgExpr::Value(Cfg::from(("target_vendor", "unknown"))),
-    ])
+// Expands cfg_attrs and removes everything which is configured out,
+// the same way rustc processes the crate before name resolution.
+pub(crate) fn configure(items: &mut Vec<syn::Item>, cfgs: &CfgSet) {
+    use syn::Item;
+    configure_vec(items, item_attrs, cfgs);
+    for item in items {
+        match item {
+            Item::Mod(item) => {
+                if let Some((_, content)) = &mut item.content {
+                    configure(content, cfgs);
+                }
+            }
+            Item::Struct(item) => configure_fields(&mut item.fields, cfgs),
+            Item::Union(item) => {
+                configure_punctuated(&mut item.fields.named, |f| &mut f.attrs, cfgs)
+            }
+            Item::Enum(item) => {
+                configure_punctuated(&mut item.variants, |v| &mut v.attrs, cfgs);
+                for variant in &mut item.variants {
+                    configure_fields(&mut variant.fields, cfgs);
+                }
+            }
+            Item::Impl(item) => configure_vec(&mut item.items, impl_item_attrs, cfgs),
+            Item::Trait(item) => configure_vec(&mut item.items, trait_item_attrs, cfgs),
+            Item::ForeignMod(item) => configure_vec(&mut item.items, foreign_item_attrs, cfgs),
+            _ => {}
+        }
+    }
 }
 
-pub(crate) fn dev_platform_name() -> String {
-    String::from("x86_64-unknown-linux-gnu")
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_configure() {
+        let mut file: syn::File = syn::parse_str(
+            "#[cfg(windows)] struct Foo; \
+             #[cfg(unix)] struct Foo(#[cfg(feature = \"serde\")] u8, u16); \
+             #[cfg(all(unix, not(feature = \"std\")))] fn no_std() {} \
+             mod inner { #[cfg(any(windows, target_os = \"linux\"))] const X: u8 = 0; } \
+             #[cfg_attr(unix, cfg(feature = \"serde\"), doc = \"gone\")] struct Bar; \
+             #[cfg_attr(all(unix, feature = \"std\"), path = \"unix.rs\", \
+                        cfg_attr(windows, macro_use))] mod sys;",
+        )
+        .unwrap();
+        let cfgs: CfgSet = vec![
+            Cfg::from("unix"),
+            Cfg::from(("target_os", "linux")),
+            Cfg::from(("feature", "std")),
+        ]
+        .into_iter()
+        .collect();
+        configure(&mut file.items, &cfgs);
+        assert_eq!(file.items.len(), 3);
+        match &file.items[0] {
+            syn::Item::Struct(item) => assert_eq!(item.fields.len(), 1),
+            _ => panic!("expected the unix Foo"),
+        }
+        match &file.items[1] {
+            syn::Item::Mod(item) => assert_eq!(item.content.as_ref().unwrap().1.len(), 1),
+            _ => panic!("expected mod inner"),
+        }
+        match &file.items[2] {
+            syn::Item::Mod(item) => {
+                assert_eq!(item.attrs.len(), 1);
+                assert!(item.attrs[0].path.is_ident("path"));
+            }
+            _ => panic!("expected mod sys"),
+        }
+    }
+
+    #[test]
+    fn test_parse_and_simplify() {
+        let source = "all(unix, not(any(target_os = \"linux\", windows)), feature = \"std\")";
+        let expr: CfgExpr = source.parse().unwrap();
+        assert_eq!(expr.to_string(), source);
+        assert_eq!(expr.to_string().parse::<CfgExpr>().unwrap(), expr);
+        assert!("all(unix".parse::<CfgExpr>().is_err());
+        assert_eq!(
+            "target_os = \"linux\"".parse::<Cfg>().unwrap(),
+            Cfg::from(("target_os", "linux"))
+        );
+
+        let cfgs: CfgSet = vec![Cfg::from("unix"), Cfg::from(("target_os", "macos"))]
+            .into_iter()
+            .collect();
+        assert!(!expr.eval(&cfgs));
+        assert!("any(unix, windows)".parse::<CfgExpr>().unwrap().eval(&cfgs));
+
+        let simplify = |s: &str| s.parse::<CfgExpr>().unwrap().simplify().to_string();
+        assert_eq!(
+            simplify("all(unix, all(not(not(windows)), unix), all())"),
+            "all(unix, windows)"
+        );
+        assert_eq!(simplify("any(unix, any(not(unix), windows))"), "all()");
+        assert_eq!(simplify("all(unix, not(all()))"), "any()");
+        assert_eq!(
+            simplify("any(all(target_os = \"linux\"))"),
+            "target_os = \"linux\""
+        );
+        assert!("all(unix, not(unix))"
+            .parse::<CfgExpr>()
+            .unwrap()
+            .simplify()
+            .is_always_false());
+    }
 }
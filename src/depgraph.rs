@@ -10,17 +10,56 @@ use std::{
 };
 
 use crate::{
-    cargo::{parse_cargo, DependentPackage},
+    cargo::{
+        cargo_config, crates_io_source, parse_cargo, DependentPackage, Resolver, SourceOverrides,
+    },
     depgraph::dag::{Dag, Node},
-    error::Result,
+    error::{Error, Result},
+    lockfile::Lockfile,
+    options::Options,
+    patch::Patches,
     printer::TreePrintable,
+    target::TargetSpec,
+    workspace::Workspace,
+};
+use cargo::{
+    core::{dependency::DepKind, Package, PackageId, Source},
+    Config,
 };
-use cargo::{core::Package, Config};
 
 #[derive(Eq, Clone)]
 struct Crate {
     pkg: DependentPackage,
-    dependencies: Vec<Crate>,
+    dependencies: Vec<DepEdge>,
+}
+
+// A dependency along with the table it is declared in
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+struct DepEdge {
+    crt: Crate,
+    kind: DepKind,
+}
+
+impl Display for DepEdge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.crt)?;
+        match self.kind {
+            DepKind::Development => write!(f, " (dev)"),
+            // Otherwise already marked as built for the host
+            DepKind::Build if !self.crt.pkg.is_host() => write!(f, " (build)"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl TreePrintable for DepEdge {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        self.crt.children()
+    }
 }
 
 impl Crate {
@@ -32,26 +71,52 @@ impl Crate {
         }
     }
 
-    // Assumption: dep isn't already a dependency
-    fn add_dependency(&mut self, dep: Crate) {
-        self.dependencies.push(dep);
+    // Assumption: dep isn't already a dependency of this kind
+    fn add_dependency(&mut self, dep: Crate, kind: DepKind) {
+        self.dependencies.push(DepEdge { crt: dep, kind });
     }
 
     // Current package and all unique dependencies
     fn sub_crates(&self) -> HashSet<&DependentPackage> {
         let mut pkgs = HashSet::from_iter([&self.pkg]);
         for dep in &self.dependencies {
-            pkgs.extend(dep.sub_crates());
+            pkgs.extend(dep.crt.sub_crates());
         }
         pkgs
     }
 
-    fn direct_dependencies(&self, pkg: &DependentPackage) -> Option<Vec<&DependentPackage>> {
+    // Replaces every package in the tree by its unified node, which
+    // has all the features and targets any of its dependents asked for
+    fn unified(&self, crates: &HashMap<CrateKey, Crate>) -> Crate {
+        let mut unified = crates
+            .get(&crate_key(&self.pkg))
+            .cloned()
+            .unwrap_or_else(|| self.clone());
+        unified.dependencies = unified
+            .dependencies
+            .iter()
+            .map(|dep| DepEdge {
+                crt: dep.crt.unified(crates),
+                kind: dep.kind,
+            })
+            .collect();
+        unified
+    }
+
+    fn direct_dependencies(
+        &self,
+        pkg: &DependentPackage,
+    ) -> Option<Vec<(&DependentPackage, DepKind)>> {
         if &self.pkg == pkg {
-            Some(self.dependencies.iter().map(|crt| &crt.pkg).collect())
+            Some(
+                self.dependencies
+                    .iter()
+                    .map(|dep| (&dep.crt.pkg, dep.kind))
+                    .collect(),
+            )
         } else {
             for dep in &self.dependencies {
-                let ans = dep.direct_dependencies(pkg);
+                let ans = dep.crt.direct_dependencies(pkg);
                 if ans.is_some() {
                     return ans;
                 }
@@ -81,7 +146,14 @@ impl PartialOrd for Crate {
 
 impl Display for Crate {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{} v{}", self.pkg.name(), self.pkg.version())
+        write!(f, "{} v{}", self.pkg.name(), self.pkg.version())?;
+        if self.pkg.is_host() {
+            write!(f, " (build)")?;
+        }
+        if self.pkg.is_patched() {
+            write!(f, " (patched)")?;
+        }
+        Ok(())
     }
 }
 
@@ -99,74 +171,345 @@ impl TreePrintable for Crate {
 }
 
 pub struct DepGraph {
-    root: Crate,
+    // One for a package, or one per member for a workspace
+    roots: Vec<Crate>,
+    targets: Vec<TargetSpec>,
+    resolver: Resolver,
 }
 
 impl Display for DepGraph {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        self.root.tree_print(f)
+        for (idx, root) in self.roots.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            root.tree_print(f)?;
+        }
+        Ok(())
     }
 }
 
 impl DepGraph {
     pub fn new<T: AsRef<StdPath>>(crate_root: T) -> Result<Self> {
-        let config = Config::default()?;
-        let (manifest, manifest_path) = parse_cargo(&crate_root, &config)?;
-        let root_pkg = DependentPackage::default_from_cargo(Package::new(manifest, &manifest_path));
+        Self::with_options(crate_root, Options::default())
+    }
+
+    pub fn with_target<T: AsRef<StdPath>>(crate_root: T, target: TargetSpec) -> Result<Self> {
+        Self::with_options(crate_root, Options::new().target(target))
+    }
+
+    pub fn with_targets<T: AsRef<StdPath>>(
+        crate_root: T,
+        targets: Vec<TargetSpec>,
+    ) -> Result<Self> {
+        Self::with_options(crate_root, Options::new().targets(targets))
+    }
+
+    // With several targets, this is the union of the graphs for each
+    // of them. Every package records which targets it is a dependency on.
+    // For a workspace root, every member (or those selected in options)
+    // is a root of the graph, and they share the packages they depend on.
+    pub fn with_options<T: AsRef<StdPath>>(crate_root: T, options: Options) -> Result<Self> {
+        let Options {
+            targets,
+            features,
+            ignore_lockfile,
+            offline,
+            local_source,
+            members,
+            dep_kinds,
+        } = options;
+        let config = cargo_config(&crate_root, offline)?;
+        let (packages, resolver, workspace_root) = match Workspace::load(&crate_root)? {
+            Some(workspace) => {
+                let packages = workspace_members(&workspace, &members, &config)?;
+                (
+                    packages,
+                    workspace.resolver(),
+                    workspace.root().to_path_buf(),
+                )
+            }
+            None => {
+                let (manifest, manifest_path) = parse_cargo(&crate_root, &config)?;
+                let resolver = Resolver::from_manifest(&manifest);
+                // Members of a workspace share its Cargo.lock
+                let lock_root = match Workspace::find(&crate_root)? {
+                    Some(workspace) => workspace.root().to_path_buf(),
+                    None => crate_root.as_ref().to_path_buf(),
+                };
+                (
+                    vec![Package::new(manifest, &manifest_path)],
+                    resolver,
+                    lock_root,
+                )
+            }
+        };
+        let lockfile = if ignore_lockfile {
+            None
+        } else {
+            Lockfile::load(&workspace_root)?
+        };
+        let patches = Patches::load(&workspace_root, &config)?;
+        let root_pkgs = if packages.len() == 1 {
+            vec![DependentPackage::from_cargo_with_features(
+                packages[0].clone(),
+                &features,
+            )?]
+        } else {
+            features.check_any(&packages.iter().collect::<Vec<_>>())?;
+            packages
+                .iter()
+                .map(|pkg| {
+                    DependentPackage::from_cargo_with_features(
+                        pkg.clone(),
+                        &features.for_package(pkg),
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
         let crates = RefCell::new(HashMap::new());
+        let ctx = GraphContext {
+            config: &config,
+            targets: &targets,
+            kinds: &dep_kinds,
+            resolver,
+            crates_io: RefCell::new(crates_io_source(&config)?),
+            overrides: SourceOverrides::new(
+                lockfile.as_ref(),
+                local_source.as_ref(),
+                Some(&patches),
+            ),
+        };
+        let mut roots = Vec::new();
+        for mut root_pkg in root_pkgs {
+            root_pkg.set_targets(
+                targets
+                    .iter()
+                    .map(|target| String::from(target.triple()))
+                    .collect(),
+            );
+            roots.push(graph_node(root_pkg, &ctx, &crates, 0)?);
+        }
+        // Only the dev-dependencies of the roots are built, and they are
+        // kept out of the shared nodes, since they can form cycles
+        let mut dev_deps = Vec::new();
+        for root in &roots {
+            if dep_kinds.contains(&DepKind::Development) {
+                dev_deps.push(dev_dependencies(&root.pkg, &ctx, &crates)?);
+            } else {
+                dev_deps.push(Vec::new());
+            }
+        }
+        let roots = roots
+            .iter()
+            .zip(dev_deps)
+            .map(|(root, dev_deps)| {
+                let mut root = root.unified(&crates.borrow());
+                for dev_dep in dev_deps {
+                    root.add_dependency(dev_dep.unified(&crates.borrow()), DepKind::Development);
+                }
+                root.dependencies.sort();
+                root
+            })
+            .collect();
+        // The context, with its crates.io source, borrows targets
+        drop(ctx);
         Ok(DepGraph {
-            root: rec_graph_create(&root_pkg, &config, &crates, 0)?,
+            roots,
+            targets,
+            resolver,
         })
     }
 
     pub fn crates(&self) -> HashSet<&DependentPackage> {
-        self.root.sub_crates()
+        self.roots.iter().flat_map(Crate::sub_crates).collect()
     }
 
+    // The root package, or the workspace members
+    pub fn roots(&self) -> Vec<&DependentPackage> {
+        self.roots.iter().map(|root| &root.pkg).collect()
+    }
+
+    // Not necessarily acyclic when it has dev-dependencies
     pub fn dag(&self) -> Dag<'_> {
         let mut nodes: Vec<_> = self.crates().into_iter().map(Node::free_node).collect();
 
-        let mut stack = vec![&self.root];
+        let mut stack: Vec<_> = self.roots.iter().collect();
         while !stack.is_empty() {
             let crt = stack.pop().unwrap();
             for dep in &crt.dependencies {
-                let dep_node = nodes.iter_mut().find(|n| n.pkg() == &dep.pkg).unwrap();
-                if !dep_node.has_dependent(&crt.pkg) {
-                    dep_node.add_dependent(&crt.pkg);
+                let dep_node = nodes.iter_mut().find(|n| n.pkg() == &dep.crt.pkg).unwrap();
+                if !dep_node.has_dependent(&crt.pkg, dep.kind) {
+                    dep_node.add_dependent(&crt.pkg, dep.kind);
                 }
-                stack.push(dep);
+                stack.push(&dep.crt);
             }
         }
 
         Dag::new(nodes)
     }
 
-    pub fn direct_dependencies(&self, pkg: &DependentPackage) -> Option<Vec<&DependentPackage>> {
-        self.root.direct_dependencies(pkg)
+    pub fn targets(&self) -> &[TargetSpec] {
+        &self.targets
+    }
+
+    pub fn resolver(&self) -> Resolver {
+        self.resolver
+    }
+
+    pub fn direct_dependencies(
+        &self,
+        pkg: &DependentPackage,
+    ) -> Option<Vec<(&DependentPackage, DepKind)>> {
+        self.roots
+            .iter()
+            .find_map(|root| root.direct_dependencies(pkg))
     }
 }
 
+// The packages of the members named in selected, or of all of them
+fn workspace_members(
+    workspace: &Workspace,
+    selected: &[String],
+    config: &Config,
+) -> Result<Vec<Package>> {
+    let mut packages = Vec::new();
+    for member in workspace.members() {
+        let (manifest, manifest_path) = parse_cargo(member, config)?;
+        packages.push(Package::new(manifest, &manifest_path));
+    }
+    for name in selected {
+        if !packages.iter().any(|pkg| pkg.name().as_str() == name) {
+            return Err(Error::PackageNotFound(name.clone()));
+        }
+    }
+    if !selected.is_empty() {
+        packages.retain(|pkg| selected.iter().any(|name| pkg.name().as_str() == name));
+    }
+    Ok(packages)
+}
+
+// Packages are unified by their id, which includes their
+// source, and whether they are built for the host
+type CrateKey = (PackageId, bool);
+
+fn crate_key(pkg: &DependentPackage) -> CrateKey {
+    pkg.key()
+}
+
+// What stays the same while the graph is built
+struct GraphContext<'a> {
+    config: &'a Config,
+    targets: &'a [TargetSpec],
+    kinds: &'a [DepKind],
+    resolver: Resolver,
+    crates_io: RefCell<Box<dyn Source + 'a>>,
+    overrides: SourceOverrides<'a>,
+}
+
 fn rec_graph_create(
     pkg: &DependentPackage,
-    config: &Config,
-    crates: &RefCell<HashMap<String, Crate>>,
+    ctx: &GraphContext<'_>,
+    crates: &RefCell<HashMap<CrateKey, Crate>>,
     depth: i32,
 ) -> Result<Crate> {
     let mut bare_crate = Crate::bare_crate(pkg.clone());
-    let dep_pkgs = pkg.download_dependencies(config, true)?;
-    for dep_pkg in &dep_pkgs {
-        let dep_key = dep_pkg.to_string();
-        let mut dep_crate = None;
-        if let Some(existing_dep_crate) = crates.borrow_mut().get(&dep_key) {
-            dep_crate = Some(existing_dep_crate.clone());
-        }
-        if dep_crate.is_none() {
-            let new_dep_crate = rec_graph_create(dep_pkg, config, crates, depth + 1)?;
-            crates.borrow_mut().insert(dep_key, new_dep_crate.clone());
-            dep_crate = Some(new_dep_crate);
-        }
-        bare_crate.add_dependency(dep_crate.unwrap());
+    let kinds: Vec<_> = ctx
+        .kinds
+        .iter()
+        .copied()
+        .filter(|kind| *kind != DepKind::Development)
+        .collect();
+    let dep_pkgs = pkg.download_dependencies(
+        ctx.config,
+        ctx.crates_io.borrow_mut().as_mut(),
+        ctx.targets,
+        &kinds,
+        ctx.overrides,
+    )?;
+    for (kind, dep_pkg) in dep_pkgs {
+        bare_crate.add_dependency(graph_node(dep_pkg, ctx, crates, depth + 1)?, kind);
     }
     bare_crate.dependencies.sort();
     Ok(bare_crate)
 }
+
+fn dev_dependencies(
+    pkg: &DependentPackage,
+    ctx: &GraphContext<'_>,
+    crates: &RefCell<HashMap<CrateKey, Crate>>,
+) -> Result<Vec<Crate>> {
+    let dep_pkgs = pkg.download_dependencies(
+        ctx.config,
+        ctx.crates_io.borrow_mut().as_mut(),
+        ctx.targets,
+        &[DepKind::Development],
+        ctx.overrides,
+    )?;
+    let mut dev_deps = Vec::new();
+    for (_, dep_pkg) in dep_pkgs {
+        dev_deps.push(graph_node(dep_pkg, ctx, crates, 1)?);
+    }
+    Ok(dev_deps)
+}
+
+// The crate for pkg, shared with every other use of the same package
+fn graph_node(
+    mut pkg: DependentPackage,
+    ctx: &GraphContext<'_>,
+    crates: &RefCell<HashMap<CrateKey, Crate>>,
+    depth: i32,
+) -> Result<Crate> {
+    // The version 1 resolver has a single build of every package
+    if ctx.resolver == Resolver::V1 {
+        pkg.set_host(false);
+    }
+    let key = crate_key(&pkg);
+    if let Some(existing_crate) = crates.borrow_mut().get_mut(&key) {
+        let mut unified_pkg = existing_crate.pkg.clone();
+        if unified_pkg.unify(&pkg) {
+            // Asked for with more features or on more targets than
+            // before, so its own dependencies have to be found again
+            pkg = unified_pkg;
+        } else {
+            existing_crate.pkg = unified_pkg;
+            return Ok(existing_crate.clone());
+        }
+    }
+    let new_crate = rec_graph_create(&pkg, ctx, crates, depth)?;
+    crates.borrow_mut().insert(key, new_crate.clone());
+    Ok(new_crate)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cargo::test::package;
+
+    #[test]
+    fn test_v1_proc_macro_dev_dependency() {
+        let pm = package("v1-pm", "[lib]\nproc-macro = true\n");
+        let dep = format!("v1-pm = {{ path = {:?} }}\n", pm.root());
+        let app = package(
+            "v1-app",
+            &format!("[dependencies]\n{}[dev-dependencies]\n{}", dep, dep),
+        );
+        let options = Options::new().offline().dep_kinds(vec![
+            DepKind::Normal,
+            DepKind::Build,
+            DepKind::Development,
+        ]);
+        let graph = DepGraph::with_options(app.root(), options).unwrap();
+        assert_eq!(graph.resolver, Resolver::V1);
+        let pms: Vec<_> = graph
+            .crates()
+            .into_iter()
+            .filter(|pkg| pkg.name() == "v1-pm")
+            .collect();
+        assert_eq!(pms.len(), 1);
+        assert!(!pms[0].is_host());
+        let root = &graph.roots[0];
+        let kinds: Vec<_> = root.dependencies.iter().map(|dep| dep.kind).collect();
+        assert_eq!(kinds, [DepKind::Normal, DepKind::Development]);
+    }
+}
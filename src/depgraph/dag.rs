@@ -4,12 +4,15 @@ use std::{
     io::Write,
 };
 
+use cargo::core::dependency::DepKind;
+
 use crate::{cargo::DependentPackage, error::Result};
 
 #[derive(Debug, Eq)]
 pub(super) struct Node<'pkg> {
     pkg: &'pkg DependentPackage,
-    dependents: Vec<&'pkg DependentPackage>,
+    // Along with how they depend on this
+    dependents: Vec<(&'pkg DependentPackage, DepKind)>,
 }
 
 impl PartialEq for Node<'_> {
@@ -36,12 +39,12 @@ impl<'pkg> Node<'pkg> {
         self.pkg
     }
 
-    pub(super) fn add_dependent(&mut self, node: &'pkg DependentPackage) {
-        self.dependents.push(node);
+    pub(super) fn add_dependent(&mut self, node: &'pkg DependentPackage, kind: DepKind) {
+        self.dependents.push((node, kind));
     }
 
-    pub(super) fn has_dependent(&self, node: &'pkg DependentPackage) -> bool {
-        self.dependents.contains(&node)
+    pub(super) fn has_dependent(&self, node: &'pkg DependentPackage, kind: DepKind) -> bool {
+        self.dependents.contains(&(node, kind))
     }
 }
 
@@ -66,9 +69,21 @@ impl<'pkg> Dag<'pkg> {
         writeln!(file, "digraph G {{")?;
         for node in &self.nodes {
             let from_idx = idx_map[node.pkg];
-            for dep in &node.dependents {
+            for (dep, kind) in &node.dependents {
                 let to_idx = idx_map[dep];
-                writeln!(file, "  {} -> {};", from_idx, to_idx)?;
+                match kind {
+                    DepKind::Normal => writeln!(file, "  {} -> {};", from_idx, to_idx)?,
+                    DepKind::Build => writeln!(
+                        file,
+                        "  {} -> {} [style = dashed, label = \"build\"];",
+                        from_idx, to_idx
+                    )?,
+                    DepKind::Development => writeln!(
+                        file,
+                        "  {} -> {} [style = dotted, label = \"dev\"];",
+                        from_idx, to_idx
+                    )?,
+                }
             }
             writeln!(file, "  {} [label = \"{}\"];", from_idx, node.pkg.name())?;
         }
@@ -76,6 +91,8 @@ impl<'pkg> Dag<'pkg> {
         Ok(())
     }
 
+    // Dependencies come before their dependents. Dev-dependency edges
+    // are not followed, as they may point back at what they are for.
     pub fn topological_order(&self) -> Vec<&DependentPackage> {
         let mut visited: HashMap<_, _> = self.nodes.iter().map(|n| (n.pkg, false)).collect();
         let node_map: HashMap<_, _> = self.nodes.iter().map(|n| (n.pkg, n)).collect();
@@ -88,7 +105,11 @@ impl<'pkg> Dag<'pkg> {
             pkg_order: &mut Vec<&'node DependentPackage>,
         ) {
             visited.insert(node.pkg, true);
-            for dep in &node.dependents {
+            let dependents = node
+                .dependents
+                .iter()
+                .filter(|(_, kind)| *kind != DepKind::Development);
+            for (dep, _) in dependents {
                 if !visited[dep] {
                     toposort_rec(node_map[dep], visited, node_map, pkg_order);
                 }
@@ -105,3 +126,26 @@ impl<'pkg> Dag<'pkg> {
         pkg_order
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cargo::test::package;
+
+    #[test]
+    fn test_topological_order() {
+        let app = DependentPackage::default_from_cargo(package("app", ""));
+        let log = DependentPackage::default_from_cargo(package("log", ""));
+        let tester = DependentPackage::default_from_cargo(package("tester", ""));
+        // app depends on log and has tester as a dev-dependency,
+        // which in turn depends on app
+        let mut log_node = Node::free_node(&log);
+        log_node.add_dependent(&app, DepKind::Normal);
+        let mut app_node = Node::free_node(&app);
+        app_node.add_dependent(&tester, DepKind::Normal);
+        let mut tester_node = Node::free_node(&tester);
+        tester_node.add_dependent(&app, DepKind::Development);
+        let dag = Dag::new(vec![app_node, tester_node, log_node]);
+        assert_eq!(dag.topological_order(), [&log, &app, &tester]);
+    }
+}
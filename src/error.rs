@@ -31,6 +31,21 @@ quick_error! {
         PackageNotFound(name: String) {
             display("Package not found: {}", name)
         }
+        FeatureNotFound(pkg: String, feature: String) {
+            display("Package {} does not have the feature {}", pkg, feature)
+        }
+        InvalidLockfile(msg: String) {
+            display("{}", msg)
+        }
+        NotCached(name: String, reason: String) {
+            display("Package {} is not cached and cannot be fetched offline: {}", name, reason)
+        }
+        InvalidPatch(msg: String) {
+            display("{}", msg)
+        }
+        InvalidWorkspace(msg: String) {
+            display("{}", msg)
+        }
         InvalidCrate(msg: String) {
             display("{}", msg)
         }
@@ -40,6 +55,9 @@ quick_error! {
         HomeDirNotFound(msg: &'static str) {
             display("{}", msg)
         }
+        InvalidCfg(msg: String) {
+            display("{}", msg)
+        }
         GitError(err: git2::Error) {
             from()
             source(err)
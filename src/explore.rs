@@ -1,54 +1,84 @@
 use crate::{
     cargo::{download_package_deps, parse_cargo, DependentPackage},
+    cfg::{self, Cfg, CfgSet},
     depgraph::DepGraph,
     error::{Error, Result},
     item::{
         self,
+        attrs::Attributes,
         consts::{consts_from_items, Const},
         enums::{enums_from_items, Enum},
         extern_crate::{extern_crates_from_items, ExternCrate},
+        foreign::{foreign_items_from_items, ForeignItem},
+        functions::{functions_from_items, Function, Signature},
+        impls::{impls_from_items, Impl},
+        macros::{macro_use_modules_from_items, macros_from_items, Macro},
         module::modules_from_items,
         module::Module as ModuleItem,
+        proc_macros::{proc_macros_from_items, ProcMacro},
         reexport::ReExport,
+        statics::{statics_from_items, Static},
         structs::{structs_from_items, Path, Struct, Visibility},
+        traits::{traits_from_items, Trait},
         types::{type_aliases_from_items, TypeAlias},
+        unions::{unions_from_items, Union},
         Item,
     },
+    options::Options,
     stdlib::StdRepo,
-    tree::ItemTree,
-    use_path::{use_paths_from_items, UsePath},
+    target::TargetSpec,
+    tree::{ItemTree, TreeItem},
+    use_path::{use_paths_from_items, UsePath, UsePathComponent},
 };
 use cargo::{
-    core::{compiler::CrateType, manifest::TargetSourcePath, Edition, Package, Target, TargetKind},
+    core::{
+        compiler::CrateType, dependency::DepKind, manifest::TargetSourcePath, Edition,
+        FeatureValue, Package, PackageId, Target, TargetKind,
+    },
     Config,
 };
 use colored::*;
 use log::{debug, warn};
 use rayon::prelude::*;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{self, Display, Formatter},
     fs::File,
     io::Read,
     path::{Path as StdPath, PathBuf},
 };
-use syn::{parenthesized, parse::Parse, token, LitStr, Token};
+use syn::{parse::Parse, LitStr, Token};
 
+// gen is given the attributes of the `mod` declaration of
+// the file, followed by the inner attributes of the file
 fn things_from_file<T, F, R>(
     file_path: T,
     mut module: crate::item::structs::Path,
+    outer_attrs: &Attributes,
+    cfgs: &CfgSet,
     gen: F,
 ) -> Result<Option<R>>
 where
     T: AsRef<StdPath>,
-    F: Fn(&[syn::Item], &mut Path) -> R,
+    F: Fn(&Attributes, &[syn::Item], &mut Path, &StdPath) -> R,
 {
     debug!("{}", file_path.as_ref().as_os_str().to_str().unwrap());
     let mut file = File::open(file_path.as_ref())?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
     match syn::parse_file(&contents) {
-        Ok(ast) => Ok(Some(gen(&ast.items, &mut module))),
+        Ok(mut ast) => {
+            cfg::configure(&mut ast.items, cfgs);
+            let attrs = outer_attrs
+                .clone()
+                .followed_by(Attributes::from_syn(&ast.attrs));
+            Ok(Some(gen(
+                &attrs,
+                &ast.items,
+                &mut module,
+                file_path.as_ref(),
+            )))
+        }
         Err(err) => {
             warn!("{}", err);
             Ok(None)
@@ -60,10 +90,13 @@ struct SimplePackage {
     targets: Vec<SimpleTarget>,
     name: String,
     edition: Edition,
+    // The cfgs items are evaluated against: those of the
+    // target platform, plus the enabled features
+    cfgs: CfgSet,
 }
 
 impl SimplePackage {
-    fn from_cargo(pkg: Package) -> Self {
+    fn from_cargo(pkg: Package, target: &TargetSpec) -> Self {
         let targets: Vec<SimpleTarget> =
             pkg.targets().iter().map(SimpleTarget::from_cargo).collect();
         let manifest = pkg.manifest();
@@ -71,6 +104,7 @@ impl SimplePackage {
             targets,
             name: String::from(pkg.name().as_str()),
             edition: manifest.edition(),
+            cfgs: target.cfgs().clone(),
         }
     }
 
@@ -87,11 +121,22 @@ impl SimplePackage {
     fn name(&self) -> &String {
         &self.name
     }
-}
 
-impl From<&DependentPackage> for SimplePackage {
-    fn from(dpkg: &DependentPackage) -> Self {
-        Self::from_cargo(dpkg.package().clone())
+    fn is_proc_macro(&self) -> bool {
+        self.library().map_or(false, |lib| {
+            SimpleTargetKind::from(lib.kind.clone()) == SimpleTargetKind::ProcMacro
+        })
+    }
+
+    fn from_dependent(dpkg: &DependentPackage, target: &TargetSpec) -> Self {
+        let mut spkg = Self::from_cargo(dpkg.package().clone(), target);
+        for feature in dpkg.enabled_features() {
+            if let FeatureValue::Feature(name) = feature {
+                spkg.cfgs
+                    .insert(Cfg::KeyValue(String::from("feature"), name.to_string()));
+            }
+        }
+        spkg
     }
 }
 
@@ -123,6 +168,7 @@ impl SimpleTarget {
 pub enum SimpleTargetKind {
     Binary,
     Library,
+    ProcMacro,
     ExampleLib,
     ExampleBin,
     Benchmark,
@@ -133,6 +179,9 @@ pub enum SimpleTargetKind {
 impl From<TargetKind> for SimpleTargetKind {
     fn from(kind: TargetKind) -> SimpleTargetKind {
         match kind {
+            TargetKind::Lib(crate_types) if crate_types.contains(&CrateType::ProcMacro) => {
+                SimpleTargetKind::ProcMacro
+            }
             TargetKind::Lib(_) => SimpleTargetKind::Library,
             TargetKind::Bin => SimpleTargetKind::Binary,
             TargetKind::Test => SimpleTargetKind::Test,
@@ -149,6 +198,7 @@ impl Display for SimpleTargetKind {
         match self {
             SimpleTargetKind::Binary => write!(f, "Binary"),
             SimpleTargetKind::Library => write!(f, "Library"),
+            SimpleTargetKind::ProcMacro => write!(f, "ProcMacro"),
             SimpleTargetKind::ExampleLib => write!(f, "ExampleLib"),
             SimpleTargetKind::ExampleBin => write!(f, "ExampleBin"),
             SimpleTargetKind::Benchmark => write!(f, "Benchmark"),
@@ -168,6 +218,7 @@ fn simple_package_for_std(lib_path: PathBuf) -> SimplePackage {
         targets: vec![lib_target],
         name: String::from("std"),
         edition: Edition::Edition2018,
+        cfgs: TargetSpec::default().cfgs().clone(),
     }
 }
 
@@ -237,24 +288,121 @@ fn simple_package_for_std(lib_path: PathBuf) -> SimplePackage {
 pub struct CrateInfo {
     pkgs: Vec<DependentPackage>,
     items: Vec<Item>,
+    // The package each of items is from, as the items of versions of a
+    // crate share paths, and whether it is the build for the host
+    item_pkgs: Vec<(PackageId, bool)>,
+    // Triples of the targets each of items is active on
+    item_targets: Vec<Vec<String>>,
+    impls: Vec<Impl>,
+    // Likewise, the package each of impls is in
+    impl_pkgs: Vec<(PackageId, bool)>,
+    targets: Vec<TargetSpec>,
 }
 
+// Items of different targets are taken to be the same if they are
+// from the same package, written in the same place and look the same.
+#[derive(PartialEq, Eq, Hash)]
+struct MergeKey((PackageId, bool), Option<item::span::Span>, String);
+
 impl CrateInfo {
     fn empty() -> Self {
         Self {
             pkgs: Vec::new(),
             items: Vec::new(),
+            item_pkgs: Vec::new(),
+            item_targets: Vec::new(),
+            impls: Vec::new(),
+            impl_pkgs: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    // Adds the items and impls found for target. Occurrences of an item
+    // are matched up in order, so that the same item appearing twice on
+    // one target is kept twice.
+    fn merge(&mut self, other: CrateInfo, target: &TargetSpec) {
+        let mut indices: HashMap<MergeKey, Vec<usize>> = HashMap::new();
+        for (idx, (item, pkg)) in self.items.iter().zip(&self.item_pkgs).enumerate() {
+            let key = MergeKey(*pkg, item.span().cloned(), item.to_string());
+            indices.entry(key).or_default().push(idx);
+        }
+        let mut seen: HashMap<MergeKey, usize> = HashMap::new();
+        for (item, pkg) in other.items.into_iter().zip(other.item_pkgs) {
+            let key = MergeKey(pkg, item.span().cloned(), item.to_string());
+            let existing = indices.get(&key);
+            let occurrence = seen.entry(key).or_default();
+            let existing = existing.and_then(|idxs| idxs.get(*occurrence));
+            *occurrence += 1;
+            match existing {
+                Some(&idx) => self.item_targets[idx].push(String::from(target.triple())),
+                None => {
+                    self.items.push(item);
+                    self.item_pkgs.push(pkg);
+                    self.item_targets.push(vec![String::from(target.triple())]);
+                }
+            }
+        }
+
+        let mut impl_indices: HashMap<MergeKey, usize> = HashMap::new();
+        for (imp, pkg) in self.impls.iter().zip(&self.impl_pkgs) {
+            let key = MergeKey(*pkg, Some(imp.span().clone()), imp.to_string());
+            *impl_indices.entry(key).or_default() += 1;
+        }
+        for (imp, pkg) in other.impls.into_iter().zip(other.impl_pkgs) {
+            let key = MergeKey(pkg, Some(imp.span().clone()), imp.to_string());
+            match impl_indices.get_mut(&key) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => {
+                    self.impls.push(imp);
+                    self.impl_pkgs.push(pkg);
+                }
+            }
         }
     }
 
     pub fn new<P: AsRef<StdPath>>(crate_root: P) -> Result<Self> {
-        let dep_graph = DepGraph::new(crate_root)?;
+        Self::with_options(crate_root, Options::default())
+    }
+
+    pub fn with_target<P: AsRef<StdPath>>(crate_root: P, target: TargetSpec) -> Result<Self> {
+        Self::with_options(crate_root, Options::new().target(target))
+    }
+
+    pub fn with_targets<P: AsRef<StdPath>>(
+        crate_root: P,
+        targets: Vec<TargetSpec>,
+    ) -> Result<Self> {
+        Self::with_options(crate_root, Options::new().targets(targets))
+    }
+
+    // Explores the crates separately for each target, then puts
+    // together what was found, noting the targets each item is on.
+    // crate_root may be a workspace, in which case all its members
+    // are explored unless Options::members() picks some of them.
+    pub fn with_options<P: AsRef<StdPath>>(crate_root: P, options: Options) -> Result<Self> {
+        let dep_graph = DepGraph::with_options(crate_root, options)?;
         let dag = dep_graph.dag();
         let sorted_crates = dag.topological_order();
         let mut crate_info = CrateInfo::empty();
-        for dep_pkg in sorted_crates {
-            crate_info_internal(dep_pkg, &mut crate_info)?;
+        for target in dep_graph.targets() {
+            let mut target_info = CrateInfo::empty();
+            for dep_pkg in &sorted_crates {
+                if dep_pkg.is_active_on(target.triple()) {
+                    // Build and dev-dependencies cannot be used from the library
+                    let deps: Vec<_> = dep_graph
+                        .direct_dependencies(dep_pkg)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|(_, kind)| *kind == DepKind::Normal)
+                        .map(|(dep, _)| dep)
+                        .collect();
+                    crate_info_internal(dep_pkg, &deps, target, &mut target_info)?;
+                }
+            }
+            crate_info.merge(target_info, target);
         }
+        crate_info.pkgs = sorted_crates.into_iter().cloned().collect();
+        crate_info.targets = dep_graph.targets().to_vec();
         Ok(crate_info)
     }
 
@@ -262,47 +410,218 @@ impl CrateInfo {
         &self.items
     }
 
+    // Each item with the package it is from, by name, version and source.
+    // The items of a package built both for the host and the target
+    // appear once for each.
+    pub fn items_with_packages(&self) -> impl Iterator<Item = (&Item, PackageId)> {
+        self.items
+            .iter()
+            .zip(self.item_pkgs.iter().map(|(pkg, _)| *pkg))
+    }
+
+    pub fn package_items<'a>(&'a self, pkg: &DependentPackage) -> impl Iterator<Item = &'a Item> {
+        let key = pkg.key();
+        self.items
+            .iter()
+            .zip(&self.item_pkgs)
+            .filter(move |(_, item_pkg)| **item_pkg == key)
+            .map(|(item, _)| item)
+    }
+
+    // Each item with the triples of the targets it is active on
+    pub fn items_with_targets(&self) -> impl Iterator<Item = (&Item, &[String])> {
+        self.items
+            .iter()
+            .zip(self.item_targets.iter().map(Vec::as_slice))
+    }
+
+    // Items which are not active on all of the targets
+    pub fn target_specific_items(&self) -> impl Iterator<Item = (&Item, &[String])> {
+        let all_targets = self.targets.len();
+        self.items_with_targets()
+            .filter(move |(_, item_targets)| item_targets.len() < all_targets)
+    }
+
+    pub fn targets(&self) -> &[TargetSpec] {
+        &self.targets
+    }
+
     pub fn pkgs(&self) -> &[DependentPackage] {
         &self.pkgs
     }
+
+    // Packages which are not a dependency on all of the targets
+    pub fn target_specific_pkgs(&self) -> impl Iterator<Item = &DependentPackage> {
+        let all_targets = self.targets.len();
+        self.pkgs
+            .iter()
+            .filter(move |pkg| pkg.targets().len() < all_targets)
+    }
+
+    pub fn impls(&self) -> &[Impl] {
+        &self.impls
+    }
+
+    // Each impl block with the package it is in
+    pub fn impls_with_packages(&self) -> impl Iterator<Item = (&Impl, PackageId)> {
+        self.impls
+            .iter()
+            .zip(self.impl_pkgs.iter().map(|(pkg, _)| *pkg))
+    }
+
+    // All impl blocks whose self type resolved to the type at type_path
+    // in pkg, irrespective of the module or crate they live in. Those
+    // of a package built both for the host and the target are given once.
+    pub fn impls_for<'a>(
+        &'a self,
+        (pkg, type_path): (PackageId, &'a Path),
+    ) -> impl Iterator<Item = &'a Impl> {
+        let mut seen = HashSet::new();
+        self.impls.iter().filter(move |imp| {
+            imp.self_package() == Some(pkg)
+                && imp.self_path() == Some(type_path)
+                && seen.insert(imp.span())
+        })
+    }
+
+    pub fn inherent_methods<'a>(
+        &'a self,
+        ty: (PackageId, &'a Path),
+    ) -> impl Iterator<Item = (&'a Visibility, &'a Signature)> {
+        self.impls_for(ty)
+            .filter(|imp| imp.is_inherent())
+            .flat_map(Impl::methods)
+    }
+
+    pub fn implemented_traits<'a>(
+        &'a self,
+        ty: (PackageId, &'a Path),
+    ) -> impl Iterator<Item = &'a Impl> {
+        self.impls_for(ty)
+            .filter(|imp| !imp.is_inherent() && !imp.is_negative())
+    }
 }
 
-fn crate_info_internal(pkg: &DependentPackage, prev_info: &mut CrateInfo) -> Result<()> {
-    let spkg = SimplePackage::from(pkg);
+fn crate_info_internal(
+    pkg: &DependentPackage,
+    deps: &[&DependentPackage],
+    target: &TargetSpec,
+    prev_info: &mut CrateInfo,
+) -> Result<()> {
+    let spkg = SimplePackage::from_dependent(pkg, target);
 
     fn things_in_package_flat<R, F>(pkg: &SimplePackage, gen: F) -> Result<Vec<R>>
     where
-        F: Fn(&[syn::Item], &mut Path) -> HashMap<Path, Vec<R>> + Sync + Send + Copy,
+        F: Fn(&[syn::Item], &mut Path, &StdPath) -> HashMap<Path, Vec<R>> + Sync + Send + Copy,
         R: Send,
     {
         let things = things_in_package(pkg, true, gen)?;
         Ok(things.into_values().flatten().collect())
     }
 
+    let unions = things_in_package_flat(&spkg, unions_from_items)?;
     let structs = things_in_package_flat(&spkg, structs_from_items)?;
     let enums = things_in_package_flat(&spkg, enums_from_items)?;
+    let statics = things_in_package_flat(&spkg, statics_from_items)?;
     let consts = things_in_package_flat(&spkg, consts_from_items)?;
     let type_aliases = things_in_package_flat(&spkg, type_aliases_from_items)?;
-    let modules = things_in_package_flat(&spkg, modules_from_items)?;
+    let foreign_items = things_in_package_flat(&spkg, foreign_items_from_items)?;
+    let functions = things_in_package_flat(&spkg, functions_from_items)?;
+    let mut macros = things_in_package_flat(&spkg, macros_from_items)?;
+    let macro_use_modules: HashSet<_> =
+        things_in_package_flat(&spkg, macro_use_modules_from_items)?
+            .into_iter()
+            .collect();
+    for mac in &mut macros {
+        mac.hoist(&macro_use_modules);
+    }
+    let proc_macros = if spkg.is_proc_macro() {
+        things_in_package_flat(&spkg, proc_macros_from_items)?
+    } else {
+        Vec::new()
+    };
+    let traits = things_in_package_flat(&spkg, traits_from_items)?;
+    let modules: Vec<_> = things_in_package_with_attrs(&spkg, true, modules_from_items)?
+        .into_values()
+        .flatten()
+        .collect();
     let extern_crates = things_in_package(&spkg, true, extern_crates_from_items)?;
 
     let structs_tree = ItemTree::new(&structs);
+    let unions_tree = ItemTree::new(&unions);
     let enums_tree = ItemTree::new(&enums);
     let consts_tree = ItemTree::new(&consts);
+    let statics_tree = ItemTree::new(&statics);
     let type_aliases_tree = ItemTree::new(&type_aliases);
+    let functions_tree = ItemTree::new(&functions);
+    let foreign_items_tree = ItemTree::new(&foreign_items);
+    let traits_tree = ItemTree::new(&traits);
+    let proc_macros_tree = ItemTree::new(&proc_macros);
+    let macros_tree = ItemTree::new(&macros);
     let module_tree = ItemTree::new(&modules);
 
-    let use_path_resolver = UsePathResolver {
+    let use_paths = things_in_package(&spkg, true, use_paths_from_items)?;
+    let mut impls = things_in_package_flat(&spkg, impls_from_items)?;
+
+    // Dependencies are processed first, so their items are known already.
+    // Only those of the versions this package depends on, in the build for
+    // the host or the target as it uses them, are visible. They are under the
+    // names it gives them, which are not theirs if renamed in Cargo.toml.
+    let package_id = pkg.package().package_id();
+    let dep_items: Vec<_> = deps
+        .iter()
+        .filter_map(|dep| {
+            let local_name = dep
+                .renamed_in(package_id)
+                .or_else(|| dep.lib_crate_name())?;
+            let dep_key = dep.key();
+            let items: Vec<_> = prev_info
+                .items
+                .iter()
+                .zip(&prev_info.item_pkgs)
+                .filter(|(_, item_pkg)| **item_pkg == dep_key)
+                .map(|(item, _)| item)
+                .collect();
+            Some((local_name, items))
+        })
+        .collect();
+
+    let mut use_path_resolver = UsePathResolver {
         structs_tree,
+        unions_tree,
         enums_tree,
         consts_tree,
+        statics_tree,
         type_aliases_tree,
+        functions_tree,
+        foreign_items_tree,
+        traits_tree,
+        proc_macros_tree,
+        macros_tree,
         module_tree,
         extern_crates,
+        use_paths: &use_paths,
         edition: spkg.edition,
+        pkg_roots: prev_info
+            .pkgs
+            .iter()
+            .chain(std::iter::once(pkg))
+            .map(|pkg| {
+                (
+                    pkg.package().root().to_path_buf(),
+                    pkg.package().package_id(),
+                )
+            })
+            .collect(),
     };
+    for (local_name, items) in &dep_items {
+        use_path_resolver.add_items(items, local_name);
+    }
+    if let Some(lib) = spkg.library() {
+        let crate_root = Path::from(vec![lib.crate_name().clone()]);
+        use_path_resolver.add_macro_use_imports(&crate_root, &dep_items);
+    }
 
-    let use_paths = things_in_package(&spkg, true, use_paths_from_items)?;
     let mut re_exports = Vec::new();
     for (path, use_paths) in &use_paths {
         for use_path in use_paths {
@@ -317,6 +636,11 @@ fn crate_info_internal(pkg: &DependentPackage, prev_info: &mut CrateInfo) -> Res
         }
     }
 
+    for imp in &mut impls {
+        use_path_resolver.resolve_impl(imp);
+    }
+
+    let items_before = prev_info.items.len();
     prev_info.items.extend(
         structs
             .into_iter()
@@ -324,10 +648,25 @@ fn crate_info_internal(pkg: &DependentPackage, prev_info: &mut CrateInfo) -> Res
             .chain(enums.into_iter().map(Item::Enum))
             .chain(consts.into_iter().map(Item::Const))
             .chain(type_aliases.into_iter().map(Item::TypeAlias))
+            .chain(functions.into_iter().map(Item::Function))
+            .chain(traits.into_iter().map(Item::Trait))
+            .chain(unions.into_iter().map(Item::Union))
+            .chain(statics.into_iter().map(Item::Static))
+            .chain(foreign_items.into_iter().map(Item::ForeignItem))
+            .chain(macros.into_iter().map(Item::Macro))
+            .chain(proc_macros.into_iter().map(Item::ProcMacro))
             .chain(modules.into_iter().map(Item::Module))
             .chain(re_exports.into_iter().map(Item::ReExport)),
     );
 
+    let new_items = prev_info.items.len() - items_before;
+    prev_info
+        .item_pkgs
+        .extend(std::iter::repeat(pkg.key()).take(new_items));
+    prev_info
+        .impl_pkgs
+        .extend(std::iter::repeat(pkg.key()).take(impls.len()));
+    prev_info.impls.extend(impls);
     prev_info.pkgs.push(pkg.clone());
 
     Ok(())
@@ -353,7 +692,7 @@ fn crate_info_internal(pkg: &DependentPackage, prev_info: &mut CrateInfo) -> Res
 //         gen: F,
 //     ) -> Result<Vec<R>>
 //     where
-//         F: Fn(&[syn::Item], &mut Path) -> HashMap<Path, Vec<R>> + Sync + Send + Copy,
+//         F: Fn(&[syn::Item], &mut Path, &StdPath) -> HashMap<Path, Vec<R>> + Sync + Send + Copy,
 //         R: Send,
 //     {
 //         let things = mapped_things_in_package_rec(pkg, sub_pkgs, gen)?;
@@ -366,7 +705,7 @@ fn crate_info_internal(pkg: &DependentPackage, prev_info: &mut CrateInfo) -> Res
 //         gen: F,
 //     ) -> Result<HashMap<Path, Vec<R>>>
 //     where
-//         F: Fn(&[syn::Item], &mut Path) -> HashMap<Path, Vec<R>> + Sync + Send + Copy,
+//         F: Fn(&[syn::Item], &mut Path, &StdPath) -> HashMap<Path, Vec<R>> + Sync + Send + Copy,
 //         R: Send,
 //     {
 //         let mut things = things_in_package(pkg, true, gen)?;
@@ -453,29 +792,89 @@ fn extern_crate_rename(
 }
 
 struct UsePathResolver<'tree> {
+    unions_tree: ItemTree<'tree, Union>,
     structs_tree: ItemTree<'tree, Struct>,
     module_tree: ItemTree<'tree, ModuleItem>,
     enums_tree: ItemTree<'tree, Enum>,
+    statics_tree: ItemTree<'tree, Static>,
     consts_tree: ItemTree<'tree, Const>,
     type_aliases_tree: ItemTree<'tree, TypeAlias>,
+    foreign_items_tree: ItemTree<'tree, ForeignItem>,
+    functions_tree: ItemTree<'tree, Function>,
+    macros_tree: ItemTree<'tree, Macro>,
+    proc_macros_tree: ItemTree<'tree, ProcMacro>,
+    traits_tree: ItemTree<'tree, Trait>,
     extern_crates: HashMap<Path, Vec<ExternCrate>>,
+    use_paths: &'tree HashMap<Path, Vec<UsePath>>,
     edition: Edition,
+    // The directories of the packages items may be from
+    pkg_roots: Vec<(PathBuf, PackageId)>,
 }
 
 impl<'tree> UsePathResolver<'tree> {
-    fn add_items(&'tree mut self, items: &'tree [Item]) {
+    // Items of a dependency, placed in the crate named root
+    fn add_items(&mut self, items: &[&'tree Item], root: &str) {
         for item in items {
-            match item {
-                Item::Struct(s) => self.structs_tree.add_item(s),
-                Item::Enum(e) => self.enums_tree.add_item(e),
-                Item::Const(c) => self.consts_tree.add_item(c),
-                Item::TypeAlias(ta) => self.type_aliases_tree.add_item(ta),
-                Item::Module(m) => self.module_tree.add_item(m),
-                Item::ReExport(_) => todo!(),
+            if let Item::ReExport(r) = item {
+                let exported_name = r.use_path().imported_name();
+                for re_item in r.items() {
+                    let name = exported_name.unwrap_or_else(|| re_item.name());
+                    self.add_item_at(re_item, &r.module().rerooted(root), name);
+                }
+            } else {
+                self.add_item_at(item, &item.module().rerooted(root), item.name());
+            }
+        }
+    }
+
+    // `#[macro_use] extern crate foo;` makes the exported macros of foo
+    // nameable from anywhere in the crate, as if they were defined at its root.
+    fn add_macro_use_imports(
+        &mut self,
+        crate_root: &Path,
+        dep_items: &[(String, Vec<&'tree Item>)],
+    ) {
+        let extern_crates = match self.extern_crates.get(crate_root) {
+            Some(extern_crates) => extern_crates,
+            None => return,
+        };
+        for extern_crate in extern_crates {
+            let macro_use = extern_crate.macro_use();
+            let items = dep_items
+                .iter()
+                .filter(|(local_name, _)| local_name == extern_crate.name())
+                .flat_map(|(_, items)| items);
+            for item in items {
+                if let Item::Macro(mac) = item {
+                    if mac.is_exported()
+                        && mac.module().components().len() == 1
+                        && macro_use.imports(mac.name())
+                    {
+                        self.macros_tree.add_item_at(crate_root, mac.name(), mac);
+                    }
+                }
             }
         }
     }
 
+    fn add_item_at(&mut self, item: &'tree Item, module: &Path, name: &str) {
+        match item {
+            Item::Struct(s) => self.structs_tree.add_item_at(module, name, s),
+            Item::Enum(e) => self.enums_tree.add_item_at(module, name, e),
+            Item::Const(c) => self.consts_tree.add_item_at(module, name, c),
+            Item::TypeAlias(ta) => self.type_aliases_tree.add_item_at(module, name, ta),
+            Item::Function(func) => self.functions_tree.add_item_at(module, name, func),
+            Item::Trait(t) => self.traits_tree.add_item_at(module, name, t),
+            Item::Union(u) => self.unions_tree.add_item_at(module, name, u),
+            Item::Static(st) => self.statics_tree.add_item_at(module, name, st),
+            Item::ForeignItem(fi) => self.foreign_items_tree.add_item_at(module, name, fi),
+            Item::Macro(mac) => self.macros_tree.add_item_at(module, name, mac),
+            Item::ProcMacro(pm) => self.proc_macros_tree.add_item_at(module, name, pm),
+            Item::Module(m) => self.module_tree.add_item_at(module, name, m),
+            Item::ReExport(_) => {}
+        }
+    }
+
     fn resolve(&'tree self, use_path: &UsePath, containing_mod: &Path) -> Vec<ResolvedPath<'tree>> {
         let mut use_path = use_path.clone();
         if self.edition >= Edition::Edition2018 {
@@ -522,6 +921,68 @@ impl<'tree> UsePathResolver<'tree> {
         }
     }
 
+    // Like resolve, but also considers the names brought into
+    // containing_mod by use declarations. Used for paths which appear
+    // in item signatures, like the self type of an impl.
+    fn resolve_in_scope(
+        &'tree self,
+        path: &UsePath,
+        containing_mod: &Path,
+    ) -> Vec<ResolvedPath<'tree>> {
+        let items = self.resolve(path, containing_mod);
+        if !items.is_empty() {
+            return items;
+        }
+        let first = match path
+            .components()
+            .first()
+            .and_then(UsePathComponent::as_name)
+        {
+            Some(first) => first,
+            None => return items,
+        };
+        if let Some(imports) = self.use_paths.get(containing_mod) {
+            for import in imports {
+                if import.imported_name() == Some(first.as_str()) {
+                    let items = self.resolve(&path.expand_import(import), containing_mod);
+                    if !items.is_empty() {
+                        return items;
+                    }
+                }
+            }
+        }
+        items
+    }
+
+    // The package the definition of rp is in, by the directory of its file
+    fn package_of(&self, rp: &ResolvedPath<'_>) -> Option<PackageId> {
+        let file = rp.span().file();
+        self.pkg_roots
+            .iter()
+            .filter(|(root, _)| file.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+            .map(|(_, pkg)| *pkg)
+    }
+
+    fn resolve_impl(&'tree self, imp: &mut Impl) {
+        let self_type = imp.self_use_path().and_then(|use_path| {
+            self.resolve_in_scope(use_path, imp.module())
+                .into_iter()
+                .find(ResolvedPath::is_type)
+        });
+        if let Some(self_type) = self_type {
+            imp.set_self_path(self_type.path(), self.package_of(&self_type));
+        }
+        let trait_def = imp.trait_use_path().and_then(|use_path| {
+            self.resolve_in_scope(use_path, imp.module())
+                .into_iter()
+                .find(|rp| matches!(rp, ResolvedPath::Trait(_)))
+        });
+        if let Some(trait_def) = trait_def {
+            imp.set_trait_path(trait_def.path(), self.package_of(&trait_def));
+        }
+    }
+
     fn resolve_internal(
         &'tree self,
         use_path: &UsePath,
@@ -552,6 +1013,48 @@ impl<'tree> UsePathResolver<'tree> {
                 .into_iter()
                 .map(|ta| ResolvedPath::TypeAlias(ta)),
         );
+        items.extend(
+            self.functions_tree
+                .resolve_use_path(use_path, start_mod)
+                .into_iter()
+                .map(|func| ResolvedPath::Function(func)),
+        );
+        items.extend(
+            self.traits_tree
+                .resolve_use_path(use_path, start_mod)
+                .into_iter()
+                .map(|t| ResolvedPath::Trait(t)),
+        );
+        items.extend(
+            self.unions_tree
+                .resolve_use_path(use_path, start_mod)
+                .into_iter()
+                .map(|u| ResolvedPath::Union(u)),
+        );
+        items.extend(
+            self.statics_tree
+                .resolve_use_path(use_path, start_mod)
+                .into_iter()
+                .map(|st| ResolvedPath::Static(st)),
+        );
+        items.extend(
+            self.foreign_items_tree
+                .resolve_use_path(use_path, start_mod)
+                .into_iter()
+                .map(|fi| ResolvedPath::ForeignItem(fi)),
+        );
+        items.extend(
+            self.macros_tree
+                .resolve_use_path(use_path, start_mod)
+                .into_iter()
+                .map(|mac| ResolvedPath::Macro(mac)),
+        );
+        items.extend(
+            self.proc_macros_tree
+                .resolve_use_path(use_path, start_mod)
+                .into_iter()
+                .map(|pm| ResolvedPath::ProcMacro(pm)),
+        );
         items.extend(
             self.module_tree
                 .resolve_use_path(use_path, start_mod)
@@ -568,6 +1071,60 @@ enum ResolvedPath<'item> {
     Enum(&'item Enum),
     Const(&'item Const),
     TypeAlias(&'item TypeAlias),
+    Function(&'item Function),
+    Trait(&'item Trait),
+    Union(&'item Union),
+    Static(&'item Static),
+    ForeignItem(&'item ForeignItem),
+    Macro(&'item Macro),
+    ProcMacro(&'item ProcMacro),
+}
+
+impl ResolvedPath<'_> {
+    fn is_type(&self) -> bool {
+        match self {
+            ResolvedPath::Struct(_)
+            | ResolvedPath::Union(_)
+            | ResolvedPath::Enum(_)
+            | ResolvedPath::TypeAlias(_) => true,
+            ResolvedPath::ForeignItem(fi) => fi.is_type(),
+            _ => false,
+        }
+    }
+
+    fn path(&self) -> Path {
+        match *self {
+            ResolvedPath::Struct(s) => s.path(),
+            ResolvedPath::Module(m) => m.path(),
+            ResolvedPath::ProcMacro(pm) => pm.path(),
+            ResolvedPath::Macro(mac) => mac.path(),
+            ResolvedPath::ForeignItem(fi) => fi.path(),
+            ResolvedPath::Static(st) => st.path(),
+            ResolvedPath::Union(u) => u.path(),
+            ResolvedPath::Enum(e) => e.path(),
+            ResolvedPath::Const(c) => c.path(),
+            ResolvedPath::TypeAlias(ta) => ta.path(),
+            ResolvedPath::Function(func) => func.path(),
+            ResolvedPath::Trait(t) => t.path(),
+        }
+    }
+
+    fn span(&self) -> &item::span::Span {
+        match *self {
+            ResolvedPath::Struct(s) => s.span(),
+            ResolvedPath::Module(m) => m.span(),
+            ResolvedPath::ProcMacro(pm) => pm.span(),
+            ResolvedPath::Macro(mac) => mac.span(),
+            ResolvedPath::ForeignItem(fi) => fi.span(),
+            ResolvedPath::Static(st) => st.span(),
+            ResolvedPath::Union(u) => u.span(),
+            ResolvedPath::Enum(e) => e.span(),
+            ResolvedPath::Const(c) => c.span(),
+            ResolvedPath::TypeAlias(ta) => ta.span(),
+            ResolvedPath::Function(func) => func.span(),
+            ResolvedPath::Trait(t) => t.span(),
+        }
+    }
 }
 
 impl Display for ResolvedPath<'_> {
@@ -575,9 +1132,16 @@ impl Display for ResolvedPath<'_> {
         match *self {
             ResolvedPath::Struct(s) => write!(f, "{}", s),
             ResolvedPath::Module(m) => write!(f, "{}", m),
+            ResolvedPath::ProcMacro(pm) => write!(f, "{}", pm),
+            ResolvedPath::Macro(mac) => write!(f, "{}", mac),
+            ResolvedPath::ForeignItem(fi) => write!(f, "{}", fi),
+            ResolvedPath::Static(st) => write!(f, "{}", st),
+            ResolvedPath::Union(u) => write!(f, "{}", u),
             ResolvedPath::Enum(e) => write!(f, "{}", e),
             ResolvedPath::Const(c) => write!(f, "{}", c),
             ResolvedPath::TypeAlias(ta) => write!(f, "{}", ta),
+            ResolvedPath::Function(func) => write!(f, "{}", func),
+            ResolvedPath::Trait(t) => write!(f, "{}", t),
         }
     }
 }
@@ -587,9 +1151,16 @@ impl From<ResolvedPath<'_>> for Item {
         match rp {
             ResolvedPath::Struct(s) => Item::Struct(s.clone()),
             ResolvedPath::Module(m) => Item::Module(m.clone()),
+            ResolvedPath::ProcMacro(pm) => Item::ProcMacro(pm.clone()),
+            ResolvedPath::Macro(mac) => Item::Macro(mac.clone()),
+            ResolvedPath::ForeignItem(fi) => Item::ForeignItem(fi.clone()),
+            ResolvedPath::Static(st) => Item::Static(st.clone()),
+            ResolvedPath::Union(u) => Item::Union(u.clone()),
             ResolvedPath::Enum(e) => Item::Enum(e.clone()),
             ResolvedPath::Const(c) => Item::Const(c.clone()),
             ResolvedPath::TypeAlias(ta) => Item::TypeAlias(ta.clone()),
+            ResolvedPath::Function(func) => Item::Function(func.clone()),
+            ResolvedPath::Trait(t) => Item::Trait(t.clone()),
         }
     }
 }
@@ -600,26 +1171,46 @@ fn things_in_package<F, R>(
     gen: F,
 ) -> Result<HashMap<Path, Vec<R>>>
 where
-    F: Fn(&[syn::Item], &mut Path) -> HashMap<Path, Vec<R>> + Sync + Send,
+    F: Fn(&[syn::Item], &mut Path, &StdPath) -> HashMap<Path, Vec<R>> + Sync + Send,
+    R: Send,
+{
+    let gen = |_: &Attributes, items: &[syn::Item], module: &mut Path, file: &StdPath| {
+        gen(items, module, file)
+    };
+    things_in_package_with_attrs(pkg, only_lib, gen)
+}
+
+// Like things_in_package, for things which need the attributes of modules
+fn things_in_package_with_attrs<F, R>(
+    pkg: &SimplePackage,
+    only_lib: bool,
+    gen: F,
+) -> Result<HashMap<Path, Vec<R>>>
+where
+    F: Fn(&Attributes, &[syn::Item], &mut Path, &StdPath) -> HashMap<Path, Vec<R>> + Sync + Send,
     R: Send,
 {
     if only_lib {
         match pkg.library() {
-            Some(lib) => Ok(things_in_target(lib, gen)?),
+            Some(lib) => Ok(things_in_target(lib, &pkg.cfgs, gen)?),
             None => Ok(HashMap::new()),
         }
     } else {
         let mut things = HashMap::new();
         for targ in pkg.targets() {
-            things.extend(things_in_target(targ, &gen)?);
+            things.extend(things_in_target(targ, &pkg.cfgs, &gen)?);
         }
         Ok(things)
     }
 }
 
-fn things_in_target<F, R>(targ: &SimpleTarget, gen: F) -> Result<HashMap<Path, Vec<R>>>
+fn things_in_target<F, R>(
+    targ: &SimpleTarget,
+    cfgs: &CfgSet,
+    gen: F,
+) -> Result<HashMap<Path, Vec<R>>>
 where
-    F: Fn(&[syn::Item], &mut Path) -> HashMap<Path, Vec<R>> + Sync + Send,
+    F: Fn(&Attributes, &[syn::Item], &mut Path, &StdPath) -> HashMap<Path, Vec<R>> + Sync + Send,
     R: Send,
 {
     let crate_name = targ.crate_name();
@@ -627,11 +1218,17 @@ where
         TargetSourcePath::Path(path) => path,
         TargetSourcePath::Metabuild => return Ok(HashMap::new()),
     };
-    let mut things = things_from_file(&src_path, Path::from(vec![crate_name.clone()]), &gen)?
-        .unwrap_or_else(|| {
-            warn!("failed to parse {}", src_path.display());
-            HashMap::new()
-        });
+    let mut things = things_from_file(
+        &src_path,
+        Path::from(vec![crate_name.clone()]),
+        &Attributes::default(),
+        cfgs,
+        &gen,
+    )?
+    .unwrap_or_else(|| {
+        warn!("failed to parse {}", src_path.display());
+        HashMap::new()
+    });
 
     let new_things = things_from_submodules(
         &Module {
@@ -640,7 +1237,9 @@ where
             rust_path: Path::from(vec![crate_name.clone()]),
             path: src_path.clone(),
             vis: Visibility::Public,
+            attrs: Attributes::default(),
         },
+        cfgs,
         &gen,
     )?;
     for (k, mut v) in new_things {
@@ -653,13 +1252,20 @@ where
     Ok(things)
 }
 
-fn things_from_submodules<F, R>(module: &Module<'_>, gen: F) -> Result<HashMap<Path, Vec<R>>>
+fn things_from_submodules<F, R>(
+    module: &Module<'_>,
+    cfgs: &CfgSet,
+    gen: F,
+) -> Result<HashMap<Path, Vec<R>>>
 where
-    F: Fn(&[syn::Item], &mut Path) -> HashMap<Path, Vec<R>> + Sync + Send + Copy,
+    F: Fn(&Attributes, &[syn::Item], &mut Path, &StdPath) -> HashMap<Path, Vec<R>>
+        + Sync
+        + Send
+        + Copy,
     R: Send,
 {
     debug!("Exploring module {}", module);
-    let empty_mods = match empty_modules_from_file(&module.path)? {
+    let empty_mods = match empty_modules_from_file(&module.path, cfgs)? {
         Some(mods) => mods,
         None => return Ok(HashMap::new()),
     };
@@ -670,19 +1276,25 @@ where
     sub_mods
         .par_iter()
         .map(|sub_mod| {
-            things_from_file(&sub_mod.path, sub_mod.rust_path.clone(), &gen)
-                .unwrap()
-                .unwrap_or_else(|| {
-                    warn!("failed to parse {}", sub_mod.path.display());
-                    HashMap::new()
-                })
+            things_from_file(
+                &sub_mod.path,
+                sub_mod.rust_path.clone(),
+                &sub_mod.attrs,
+                cfgs,
+                &gen,
+            )
+            .unwrap()
+            .unwrap_or_else(|| {
+                warn!("failed to parse {}", sub_mod.path.display());
+                HashMap::new()
+            })
         })
         .collect_into_vec(&mut things);
     let mut more_things = Vec::new();
     sub_mods
         .par_iter()
         .map(|sub_mod| {
-            things_from_submodules(sub_mod, gen).unwrap_or_else(|_| {
+            things_from_submodules(sub_mod, cfgs, gen).unwrap_or_else(|_| {
                 warn!("failed to recurse into {}", sub_mod.rust_path);
                 HashMap::new()
             })
@@ -711,6 +1323,8 @@ struct Module<'par> {
     name: &'par str,
     cat: ModuleCategory,
     vis: Visibility,
+    // Of the `mod` declaration
+    attrs: Attributes,
 }
 
 impl Display for Module<'_> {
@@ -744,7 +1358,12 @@ impl Display for ModuleCategory {
 }
 
 impl Module<'_> {
-    fn submodule<'name>(&self, name: &'name str, vis: Visibility) -> Option<Module<'name>> {
+    fn submodule<'name>(
+        &self,
+        name: &'name str,
+        vis: Visibility,
+        attrs: Attributes,
+    ) -> Option<Module<'name>> {
         let mut rust_path = self.rust_path.clone();
         rust_path.push_name(String::from(name));
         // Check the foo.rs form
@@ -761,6 +1380,7 @@ impl Module<'_> {
                 rust_path,
                 cat: ModuleCategory::Direct,
                 vis,
+                attrs,
             });
         }
 
@@ -779,6 +1399,7 @@ impl Module<'_> {
                 rust_path,
                 cat: ModuleCategory::Mod,
                 vis,
+                attrs,
             });
         }
 
@@ -811,10 +1432,11 @@ impl Module<'_> {
                     name: &ast_mod.name,
                     cat,
                     vis: ast_mod.vis.clone(),
+                    attrs: ast_mod.attrs.clone(),
                 });
             } else {
                 sub_mods.push(
-                    self.submodule(&ast_mod.name, ast_mod.vis.clone())
+                    self.submodule(&ast_mod.name, ast_mod.vis.clone(), ast_mod.attrs.clone())
                         .ok_or_else(|| {
                             Error::InvalidCrate(format!(
                                 "Failed to find sub-self {} for module {}",
@@ -832,6 +1454,7 @@ struct ASTModule {
     name: String,
     path: Option<PathBuf>,
     vis: Visibility,
+    attrs: Attributes,
 }
 
 struct PathAttr {
@@ -847,41 +1470,18 @@ impl Parse for PathAttr {
     }
 }
 
-struct CfgAttrWithPath {
-    _paren: token::Paren,
-    _cond: syn::Ident,
-    _comma: Token![,],
-    _path_word: syn::Ident,
-    _eq: Token![=],
-    path: LitStr,
-}
-
-impl Parse for CfgAttrWithPath {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let content;
-        let paren = parenthesized!(content in input);
-        let cond = content.parse()?;
-        let comma = content.parse()?;
-        let path_word = content.parse()?;
-        let eq = content.parse()?;
-        let path = content.parse()?;
-        Ok(CfgAttrWithPath {
-            _paren: paren,
-            _cond: cond,
-            _comma: comma,
-            _path_word: path_word,
-            _eq: eq,
-            path,
-        })
-    }
-}
-
-fn empty_modules_from_file<T: AsRef<StdPath>>(path: T) -> Result<Option<Vec<ASTModule>>> {
+fn empty_modules_from_file<T: AsRef<StdPath>>(
+    path: T,
+    cfgs: &CfgSet,
+) -> Result<Option<Vec<ASTModule>>> {
     let mut file = File::open(path.as_ref())?;
     let mut content = String::new();
     file.read_to_string(&mut content)?;
     match syn::parse_file(&content) {
-        Ok(ast) => {
+        Ok(mut ast) => {
+            // Expanding cfg_attr first takes care of
+            // #[cfg_attr(windows, path = "windows.rs")]
+            cfg::configure(&mut ast.items, cfgs);
             let mut emp_mods = Vec::new();
             for item in &ast.items {
                 if let syn::Item::Mod(module) = item {
@@ -900,13 +1500,6 @@ fn empty_modules_from_file<T: AsRef<StdPath>>(path: T) -> Result<Option<Vec<ASTM
                                     let path_attr: PathAttr = syn::parse2(attr.tokens.clone())?;
                                     mod_path = Some(PathBuf::from(path_attr.path.value()));
                                     break;
-                                } else if path == "cfg_attr" {
-                                    let cfg_attr: std::result::Result<CfgAttrWithPath, syn::Error> =
-                                        syn::parse2(attr.tokens.clone());
-                                    if let Ok(cfg_attr) = cfg_attr {
-                                        mod_path = Some(PathBuf::from(cfg_attr.path.value()));
-                                        break;
-                                    }
                                 }
                             }
                         }
@@ -914,6 +1507,7 @@ fn empty_modules_from_file<T: AsRef<StdPath>>(path: T) -> Result<Option<Vec<ASTM
                             name,
                             path: mod_path,
                             vis: Visibility::from_syn(&module.vis),
+                            attrs: Attributes::from_syn(&module.attrs),
                         });
                     }
                 }
@@ -926,3 +1520,52 @@ fn empty_modules_from_file<T: AsRef<StdPath>>(path: T) -> Result<Option<Vec<ASTM
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_renamed_dependency() {
+        let file = StdPath::new("lib.rs");
+        // bar-rs, depended on as `foo = { package = "bar-rs" }`
+        let dep: syn::File = syn::parse_str("pub struct X;").unwrap();
+        let dep_items: Vec<_> =
+            structs_from_items(&dep.items, &mut Path::from(vec!["bar_rs"]), file)
+                .into_values()
+                .flatten()
+                .map(Item::Struct)
+                .collect();
+        let dep_items: Vec<_> = dep_items.iter().collect();
+
+        let app: syn::File = syn::parse_str("pub use foo::X;\npub use bar_rs::X as Y;").unwrap();
+        let app_mod = Path::from(vec!["app"]);
+        let use_paths = use_paths_from_items(&app.items, &mut app_mod.clone(), file);
+        let mut resolver = UsePathResolver {
+            unions_tree: ItemTree::new(&[]),
+            structs_tree: ItemTree::new(&[]),
+            module_tree: ItemTree::new(&[]),
+            enums_tree: ItemTree::new(&[]),
+            statics_tree: ItemTree::new(&[]),
+            consts_tree: ItemTree::new(&[]),
+            type_aliases_tree: ItemTree::new(&[]),
+            foreign_items_tree: ItemTree::new(&[]),
+            functions_tree: ItemTree::new(&[]),
+            macros_tree: ItemTree::new(&[]),
+            proc_macros_tree: ItemTree::new(&[]),
+            traits_tree: ItemTree::new(&[]),
+            extern_crates: HashMap::new(),
+            use_paths: &use_paths,
+            edition: Edition::Edition2018,
+            pkg_roots: Vec::new(),
+        };
+        resolver.add_items(&dep_items, "foo");
+
+        let app_uses = &use_paths[&app_mod];
+        let items = resolver.resolve(&app_uses[0], &app_mod);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path(), Path::from(vec!["bar_rs", "X"]));
+        // It cannot be named by its own name
+        assert!(resolver.resolve(&app_uses[1], &app_mod).is_empty());
+    }
+}
@@ -1,12 +1,28 @@
 use std::fmt::{self, Display, Formatter};
 
+use crate::tree::TreeItem;
+
+use self::structs::Path;
+
+pub mod attrs;
 pub mod consts;
 pub mod enums;
 pub mod extern_crate;
+pub mod foreign;
+pub mod functions;
+pub mod generics;
+pub mod impls;
+pub mod macros;
 pub mod module;
+pub mod proc_macros;
 pub mod reexport;
+mod render;
+pub mod span;
+pub mod statics;
 pub mod structs;
+pub mod traits;
 pub mod types;
+pub mod unions;
 
 #[macro_export]
 macro_rules! from_items {
@@ -14,6 +30,7 @@ macro_rules! from_items {
         pub fn $func_name(
             items: &[syn::Item],
             module: &mut crate::item::structs::Path,
+            file: &std::path::Path,
         ) -> std::collections::HashMap<Path, Vec<$type>> {
             use std::collections::HashMap;
             use syn::Item;
@@ -22,7 +39,8 @@ macro_rules! from_items {
             for item in items {
                 match item {
                     Item::$item_name(item) => {
-                        let s = <$type>::from_syn(item, module.clone());
+                        let span = crate::item::span::Span::from_syn(item, file);
+                        let s = <$type>::from_syn(item, module.clone(), span);
                         if let Some(existing_things) = things.get_mut(module) {
                             existing_things.push(s);
                         } else {
@@ -32,7 +50,7 @@ macro_rules! from_items {
                     Item::Mod(item) => {
                         module.push_name(item.ident.to_string());
                         if let Some((_, content)) = &item.content {
-                            let new_things = $func_name(content, module);
+                            let new_things = $func_name(content, module, file);
                             things.extend(new_things);
                         }
                         module.pop();
@@ -47,8 +65,15 @@ macro_rules! from_items {
 
 pub enum Item {
     Struct(structs::Struct),
+    Union(unions::Union),
     Enum(enums::Enum),
     Const(consts::Const),
+    Static(statics::Static),
+    Function(functions::Function),
+    Trait(traits::Trait),
+    ForeignItem(foreign::ForeignItem),
+    Macro(macros::Macro),
+    ProcMacro(proc_macros::ProcMacro),
     TypeAlias(types::TypeAlias),
     Module(module::Module),
     ReExport(reexport::ReExport),
@@ -58,11 +83,95 @@ impl Display for Item {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Item::Struct(s) => write!(f, "{}", s),
+            Item::Union(u) => write!(f, "{}", u),
             Item::Module(m) => write!(f, "{}", m),
             Item::Enum(e) => write!(f, "{}", e),
             Item::Const(c) => write!(f, "{}", c),
+            Item::Static(st) => write!(f, "{}", st),
+            Item::Function(func) => write!(f, "{}", func),
+            Item::Trait(t) => write!(f, "{}", t),
+            Item::ForeignItem(fi) => write!(f, "{}", fi),
+            Item::Macro(mac) => write!(f, "{}", mac),
+            Item::ProcMacro(pm) => write!(f, "{}", pm),
             Item::TypeAlias(ta) => write!(f, "{}", ta),
             Item::ReExport(r) => write!(f, "{}", r),
         }
     }
 }
+
+impl Item {
+    // Re-exports only point at other items, so they have none of their own
+    pub fn attrs(&self) -> Option<&attrs::Attributes> {
+        match self {
+            Item::Struct(s) => Some(s.attrs()),
+            Item::Union(u) => Some(u.attrs()),
+            Item::Module(m) => Some(m.attrs()),
+            Item::Enum(e) => Some(e.attrs()),
+            Item::Const(c) => Some(c.attrs()),
+            Item::Static(st) => Some(st.attrs()),
+            Item::Function(func) => Some(func.attrs()),
+            Item::Trait(t) => Some(t.attrs()),
+            Item::ForeignItem(fi) => Some(fi.attrs()),
+            Item::Macro(mac) => Some(mac.attrs()),
+            Item::ProcMacro(pm) => Some(pm.attrs()),
+            Item::TypeAlias(ta) => Some(ta.attrs()),
+            Item::ReExport(_) => None,
+        }
+    }
+
+    pub fn span(&self) -> Option<&span::Span> {
+        match self {
+            Item::Struct(s) => Some(s.span()),
+            Item::Union(u) => Some(u.span()),
+            Item::Module(m) => Some(m.span()),
+            Item::Enum(e) => Some(e.span()),
+            Item::Const(c) => Some(c.span()),
+            Item::Static(st) => Some(st.span()),
+            Item::Function(func) => Some(func.span()),
+            Item::Trait(t) => Some(t.span()),
+            Item::ForeignItem(fi) => Some(fi.span()),
+            Item::Macro(mac) => Some(mac.span()),
+            Item::ProcMacro(pm) => Some(pm.span()),
+            Item::TypeAlias(ta) => Some(ta.span()),
+            Item::ReExport(r) => r.span(),
+        }
+    }
+}
+
+impl TreeItem for Item {
+    fn name(&self) -> &str {
+        match self {
+            Item::Struct(s) => s.name(),
+            Item::Union(u) => u.name(),
+            Item::Module(m) => m.name(),
+            Item::Enum(e) => e.name(),
+            Item::Const(c) => c.name(),
+            Item::Static(st) => st.name(),
+            Item::Function(func) => func.name(),
+            Item::Trait(t) => t.name(),
+            Item::ForeignItem(fi) => fi.name(),
+            Item::Macro(mac) => mac.name(),
+            Item::ProcMacro(pm) => pm.name(),
+            Item::TypeAlias(ta) => ta.name(),
+            Item::ReExport(r) => r.name(),
+        }
+    }
+
+    fn module(&self) -> &Path {
+        match self {
+            Item::Struct(s) => s.module(),
+            Item::Union(u) => u.module(),
+            Item::Module(m) => m.module(),
+            Item::Enum(e) => e.module(),
+            Item::Const(c) => c.module(),
+            Item::Static(st) => st.module(),
+            Item::Function(func) => func.module(),
+            Item::Trait(t) => t.module(),
+            Item::ForeignItem(fi) => fi.module(),
+            Item::Macro(mac) => mac.module(),
+            Item::ProcMacro(pm) => pm.module(),
+            Item::TypeAlias(ta) => ta.module(),
+            Item::ReExport(r) => r.module(),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use syn::{Lit, Meta, NestedMeta};
+
+use super::render::path_to_string;
+
+// The attributes of an item which matter for its public API
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attributes {
+    doc: Option<String>,
+    doc_hidden: bool,
+    deprecated: Option<Deprecation>,
+    must_use: bool,
+    must_use_note: Option<String>,
+    non_exhaustive: bool,
+    repr: Vec<String>,
+    derives: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    since: Option<String>,
+    note: Option<String>,
+}
+
+impl Deprecation {
+    fn from_meta(meta: &Meta) -> Self {
+        let mut deprecation = Self::default();
+        match meta {
+            // #[deprecated = "note"]
+            Meta::NameValue(nv) => deprecation.note = lit_to_string(&nv.lit),
+            // #[deprecated(since = "1.0", note = "note")]
+            Meta::List(list) => {
+                for nested in &list.nested {
+                    if let NestedMeta::Meta(Meta::NameValue(nv)) = nested {
+                        if nv.path.is_ident("since") {
+                            deprecation.since = lit_to_string(&nv.lit);
+                        } else if nv.path.is_ident("note") {
+                            deprecation.note = lit_to_string(&nv.lit);
+                        }
+                    }
+                }
+            }
+            Meta::Path(_) => {}
+        }
+        deprecation
+    }
+
+    pub fn since(&self) -> Option<&String> {
+        self.since.as_ref()
+    }
+
+    pub fn note(&self) -> Option<&String> {
+        self.note.as_ref()
+    }
+}
+
+fn lit_to_string(lit: &Lit) -> Option<String> {
+    match lit {
+        Lit::Str(s) => Some(s.value()),
+        _ => None,
+    }
+}
+
+// Renders the arguments of `#[repr(...)]`, eg. `C` or `align(8)`
+fn repr_to_string(nested: &NestedMeta) -> String {
+    match nested {
+        NestedMeta::Meta(Meta::List(list)) => {
+            let args: Vec<_> = list.nested.iter().map(repr_to_string).collect();
+            format!("{}({})", path_to_string(&list.path), args.join(", "))
+        }
+        NestedMeta::Meta(meta) => path_to_string(meta.path()),
+        NestedMeta::Lit(Lit::Int(int)) => int.base10_digits().to_string(),
+        NestedMeta::Lit(lit) => lit_to_string(lit).unwrap_or_default(),
+    }
+}
+
+impl Attributes {
+    pub(crate) fn from_syn(attrs: &[syn::Attribute]) -> Self {
+        let mut attributes = Self::default();
+        let mut doc_lines = Vec::new();
+        for attr in attrs {
+            // Attributes with arbitrary tokens (eg. #[cfg_attr]) are skipped
+            let meta = match attr.parse_meta() {
+                Ok(meta) => meta,
+                Err(_) => continue,
+            };
+            let path = meta.path();
+            if path.is_ident("doc") {
+                match &meta {
+                    // `/// line` is sugar for #[doc = " line"]
+                    Meta::NameValue(nv) => {
+                        if let Some(line) = lit_to_string(&nv.lit) {
+                            doc_lines.push(line);
+                        }
+                    }
+                    // #[doc(hidden)]
+                    Meta::List(list) => {
+                        let hidden = list.nested.iter().any(|nested| match nested {
+                            NestedMeta::Meta(Meta::Path(p)) => p.is_ident("hidden"),
+                            _ => false,
+                        });
+                        attributes.doc_hidden |= hidden;
+                    }
+                    Meta::Path(_) => {}
+                }
+            } else if path.is_ident("deprecated") {
+                attributes.deprecated = Some(Deprecation::from_meta(&meta));
+            } else if path.is_ident("must_use") {
+                attributes.must_use = true;
+                if let Meta::NameValue(nv) = &meta {
+                    attributes.must_use_note = lit_to_string(&nv.lit);
+                }
+            } else if path.is_ident("non_exhaustive") {
+                attributes.non_exhaustive = true;
+            } else if path.is_ident("repr") {
+                if let Meta::List(list) = &meta {
+                    attributes
+                        .repr
+                        .extend(list.nested.iter().map(repr_to_string));
+                }
+            } else if path.is_ident("derive") {
+                if let Meta::List(list) = &meta {
+                    attributes.derives.extend(list.nested.iter().filter_map(
+                        |nested| match nested {
+                            NestedMeta::Meta(Meta::Path(p)) => Some(path_to_string(p)),
+                            _ => None,
+                        },
+                    ));
+                }
+            }
+        }
+        if !doc_lines.is_empty() {
+            let doc_lines: Vec<_> = doc_lines
+                .iter()
+                .flat_map(|line| line.lines())
+                .map(|line| line.strip_prefix(' ').unwrap_or(line))
+                .collect();
+            attributes.doc = Some(doc_lines.join("\n"));
+        }
+        attributes
+    }
+
+    // For a module in a file of its own, the attributes of its `mod`
+    // declaration are followed by the inner ones of the file
+    pub(crate) fn followed_by(mut self, inner: Self) -> Self {
+        self.doc = match (self.doc, inner.doc) {
+            (Some(outer), Some(inner)) => Some(format!("{}\n{}", outer, inner)),
+            (outer, inner) => outer.or(inner),
+        };
+        self.doc_hidden |= inner.doc_hidden;
+        self.deprecated = inner.deprecated.or(self.deprecated);
+        self.must_use |= inner.must_use;
+        self.must_use_note = inner.must_use_note.or(self.must_use_note);
+        self.non_exhaustive |= inner.non_exhaustive;
+        self.repr.extend(inner.repr);
+        self.derives.extend(inner.derives);
+        self
+    }
+
+    pub fn doc(&self) -> Option<&String> {
+        self.doc.as_ref()
+    }
+
+    pub fn is_doc_hidden(&self) -> bool {
+        self.doc_hidden
+    }
+
+    pub fn deprecated(&self) -> Option<&Deprecation> {
+        self.deprecated.as_ref()
+    }
+
+    pub fn is_must_use(&self) -> bool {
+        self.must_use
+    }
+
+    pub fn must_use_note(&self) -> Option<&String> {
+        self.must_use_note.as_ref()
+    }
+
+    pub fn is_non_exhaustive(&self) -> bool {
+        self.non_exhaustive
+    }
+
+    pub fn repr(&self) -> &[String] {
+        &self.repr
+    }
+
+    pub fn derives(&self) -> &[String] {
+        &self.derives
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_attributes() {
+        let item: syn::ItemStruct = syn::parse_str(
+            "/// Line one\n\
+             /// Line two\n\
+             #[derive(Debug, serde::Serialize)]\n\
+             #[repr(C, align(8))]\n\
+             #[non_exhaustive]\n\
+             #[deprecated(since = \"0.2.0\", note = \"use Bar\")]\n\
+             pub struct Foo;",
+        )
+        .unwrap();
+        let attrs = Attributes::from_syn(&item.attrs);
+        assert_eq!(attrs.doc().unwrap(), "Line one\nLine two");
+        assert_eq!(attrs.derives(), ["Debug", "serde::Serialize"]);
+        assert_eq!(attrs.repr(), ["C", "align(8)"]);
+        assert!(attrs.is_non_exhaustive());
+        let deprecated = attrs.deprecated().unwrap();
+        assert_eq!(deprecated.since().unwrap(), "0.2.0");
+        assert_eq!(deprecated.note().unwrap(), "use Bar");
+        assert!(!attrs.is_must_use());
+
+        let file = syn::parse_file("//! Inner\n#![doc(hidden)]\n").unwrap();
+        let attrs = attrs.followed_by(Attributes::from_syn(&file.attrs));
+        assert_eq!(attrs.doc().unwrap(), "Line one\nLine two\nInner");
+        assert!(attrs.is_doc_hidden());
+    }
+}
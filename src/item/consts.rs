@@ -2,7 +2,11 @@ use crate::from_items;
 use crate::printer::TreePrintable;
 use crate::tree::TreeItem;
 
-use super::structs::{Path, Visibility};
+use super::{
+    attrs::Attributes,
+    span::Span,
+    structs::{Path, Visibility},
+};
 use colored::*;
 
 use std::fmt::{self, Display, Formatter};
@@ -11,7 +15,9 @@ use std::fmt::{self, Display, Formatter};
 pub struct Const {
     name: String,
     vis: Visibility,
+    attrs: Attributes,
     module: Path,
+    span: Span,
 }
 
 impl Display for Const {
@@ -48,10 +54,25 @@ impl TreePrintable for Const {
 }
 
 impl Const {
-    fn from_syn(item: &syn::ItemConst, module: Path) -> Self {
+    fn from_syn(item: &syn::ItemConst, module: Path, span: Span) -> Self {
         let name = item.ident.to_string();
         let vis = Visibility::from_syn(&item.vis);
-        Self { name, vis, module }
+        let attrs = Attributes::from_syn(&item.attrs);
+        Self {
+            name,
+            vis,
+            attrs,
+            module,
+            span,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
     }
 }
 
@@ -4,14 +4,82 @@ use std::fmt::{self, Display, Formatter};
 
 use crate::{from_items, printer::TreePrintable, tree::TreeItem};
 
-use super::structs::{Path, Visibility};
+use super::{
+    attrs::Attributes,
+    generics::Generics,
+    render::tokens_to_string,
+    span::Span,
+    structs::{Fields, Path, Visibility},
+};
 
 #[derive(Debug, Clone)]
 pub struct Enum {
     name: String,
     vis: Visibility,
-    params: Vec<String>,
+    generics: Generics,
+    variants: Vec<Variant>,
+    attrs: Attributes,
     module: Path,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    name: String,
+    fields: Fields,
+    discriminant: Option<String>,
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name.yellow())?;
+        match &self.fields {
+            Fields::Named(_) => write!(f, " {{ .. }}")?,
+            Fields::Tuple(_) => write!(f, "(..)")?,
+            Fields::Unit => {}
+        }
+        if let Some(discriminant) = &self.discriminant {
+            write!(f, " = {}", discriminant)?;
+        }
+        Ok(())
+    }
+}
+
+impl TreePrintable for Variant {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        self.fields.tree_children()
+    }
+}
+
+impl Variant {
+    fn from_syn(variant: &syn::Variant) -> Self {
+        Self {
+            name: variant.ident.to_string(),
+            fields: Fields::from_syn(&variant.fields),
+            discriminant: variant
+                .discriminant
+                .as_ref()
+                .map(|(_, expr)| tokens_to_string(expr)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // The fields have no visibility of their own, they have the
+    // visibility of the enum
+    pub fn fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    pub fn discriminant(&self) -> Option<&String> {
+        self.discriminant.as_ref()
+    }
 }
 
 impl Display for Enum {
@@ -24,10 +92,7 @@ impl Display for Enum {
             self.module,
             self.name.yellow(),
         )?;
-        if !self.params.is_empty() {
-            write!(f, "<{}>", self.params.join(","))?;
-        }
-        Ok(())
+        write!(f, "{}{}", self.generics, self.generics.where_clause())
     }
 }
 
@@ -47,33 +112,59 @@ impl TreePrintable for Enum {
     }
 
     fn children(&self) -> Vec<&dyn TreePrintable> {
-        Vec::new()
+        self.variants
+            .iter()
+            .map(|variant| variant as &dyn TreePrintable)
+            .collect()
     }
 }
 
 impl Enum {
-    fn from_syn(item: &syn::ItemEnum, module: Path) -> Self {
+    fn from_syn(item: &syn::ItemEnum, module: Path, span: Span) -> Self {
         let name = item.ident.to_string();
         let vis = Visibility::from_syn(&item.vis);
-        let params: Vec<String> = item
-            .generics
-            .type_params()
-            .map(|param| param.ident.to_string())
-            .collect();
+        let generics = Generics::from_syn(&item.generics);
+        let variants = item.variants.iter().map(Variant::from_syn).collect();
         Self {
             name,
             vis,
-            params,
+            generics,
+            variants,
+            attrs: Attributes::from_syn(&item.attrs),
             module,
+            span,
         }
     }
 
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn visibility(&self) -> &Visibility {
+        &self.vis
+    }
+
+    pub fn variants(&self) -> &[Variant] {
+        &self.variants
+    }
+
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
     pub(crate) fn renamed(&self, new_name: &str) -> Self {
         Self {
             name: String::from(new_name),
             vis: self.vis.clone(),
-            params: self.params.clone(),
+            generics: self.generics.clone(),
+            variants: self.variants.clone(),
+            attrs: self.attrs.clone(),
             module: self.module.clone(),
+            span: self.span.clone(),
         }
     }
 
@@ -2,12 +2,57 @@ use std::fmt::{self, Display, Formatter};
 
 use crate::from_items;
 
-use super::structs::{Path, Visibility};
+use super::{
+    span::Span,
+    structs::{Path, Visibility},
+};
 
 pub struct ExternCrate {
     name: String,
     rename: Option<String>,
     vis: Visibility,
+    macro_use: MacroUse,
+}
+
+// Which of the crate's exported macros `#[macro_use]` brings into scope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroUse {
+    None,
+    All,
+    Only(Vec<String>),
+}
+
+impl MacroUse {
+    fn from_attrs(attrs: &[syn::Attribute]) -> Self {
+        use syn::{Meta, NestedMeta};
+
+        let attr = match attrs.iter().find(|attr| attr.path.is_ident("macro_use")) {
+            Some(attr) => attr,
+            None => return MacroUse::None,
+        };
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) => MacroUse::Only(
+                list.nested
+                    .iter()
+                    .filter_map(|nested| match nested {
+                        NestedMeta::Meta(Meta::Path(path)) => {
+                            path.get_ident().map(|ident| ident.to_string())
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => MacroUse::All,
+        }
+    }
+
+    pub fn imports(&self, name: &str) -> bool {
+        match self {
+            MacroUse::None => false,
+            MacroUse::All => true,
+            MacroUse::Only(names) => names.iter().any(|n| n == name),
+        }
+    }
 }
 
 impl Display for ExternCrate {
@@ -21,11 +66,12 @@ impl Display for ExternCrate {
 }
 
 impl ExternCrate {
-    fn from_syn(item: &syn::ItemExternCrate, _module: Path) -> Self {
+    fn from_syn(item: &syn::ItemExternCrate, _module: Path, _span: Span) -> Self {
         Self {
             name: item.ident.to_string(),
             vis: Visibility::from_syn(&item.vis),
             rename: item.rename.as_ref().map(|(_, name)| name.to_string()),
+            macro_use: MacroUse::from_attrs(&item.attrs),
         }
     }
 
@@ -36,6 +82,10 @@ impl ExternCrate {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn macro_use(&self) -> &MacroUse {
+        &self.macro_use
+    }
 }
 
 from_items!(extern_crates_from_items, ExternCrate, ExternCrate);
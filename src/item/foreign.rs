@@ -0,0 +1,193 @@
+use colored::*;
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    path::Path as StdPath,
+};
+
+use crate::{printer::TreePrintable, tree::TreeItem};
+
+use super::{
+    attrs::Attributes,
+    functions::Signature,
+    render::{abi_to_string, type_to_string},
+    span::Span,
+    structs::{Path, Visibility},
+};
+
+// An item declared inside an `extern "ABI" { ... }` block
+#[derive(Debug, Clone)]
+pub struct ForeignItem {
+    name: String,
+    vis: Visibility,
+    abi: String,
+    kind: ForeignItemKind,
+    attrs: Attributes,
+    module: Path,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForeignItemKind {
+    Fn(Signature),
+    Static { mutable: bool, ty: String },
+    Type,
+}
+
+impl Display for ForeignItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} ",
+            self.vis.to_string().magenta(),
+            format!("extern \"{}\"", self.abi).green()
+        )?;
+        match &self.kind {
+            ForeignItemKind::Fn(sig) => {
+                write!(
+                    f,
+                    "{}{} {}::{}",
+                    sig.qualifiers().to_string().green(),
+                    "fn".green(),
+                    self.module,
+                    self.name.yellow()
+                )?;
+                sig.write_rest(f)
+            }
+            ForeignItemKind::Static { mutable, ty } => {
+                let mutability = if *mutable { " mut" } else { "" };
+                write!(
+                    f,
+                    "{}{} {}::{}: {}",
+                    "static".green(),
+                    mutability.green(),
+                    self.module,
+                    self.name.yellow(),
+                    ty
+                )
+            }
+            ForeignItemKind::Type => write!(
+                f,
+                "{} {}::{}",
+                "type".green(),
+                self.module,
+                self.name.yellow()
+            ),
+        }
+    }
+}
+
+impl TreeItem for ForeignItem {
+    fn module(&self) -> &Path {
+        &self.module
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TreePrintable for ForeignItem {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+impl ForeignItem {
+    fn from_syn(item: &syn::ForeignItem, abi: &str, module: Path, file: &StdPath) -> Option<Self> {
+        let (name, vis, attrs, kind) = match item {
+            syn::ForeignItem::Fn(item) => (
+                item.sig.ident.to_string(),
+                &item.vis,
+                &item.attrs,
+                ForeignItemKind::Fn(Signature::from_syn(&item.sig)),
+            ),
+            syn::ForeignItem::Static(item) => (
+                item.ident.to_string(),
+                &item.vis,
+                &item.attrs,
+                ForeignItemKind::Static {
+                    mutable: item.mutability.is_some(),
+                    ty: type_to_string(&item.ty),
+                },
+            ),
+            syn::ForeignItem::Type(item) => (
+                item.ident.to_string(),
+                &item.vis,
+                &item.attrs,
+                ForeignItemKind::Type,
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            name,
+            vis: Visibility::from_syn(vis),
+            abi: String::from(abi),
+            kind,
+            attrs: Attributes::from_syn(attrs),
+            module,
+            span: Span::from_syn(item, file),
+        })
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn abi(&self) -> &str {
+        &self.abi
+    }
+
+    pub fn kind(&self) -> &ForeignItemKind {
+        &self.kind
+    }
+
+    pub fn is_type(&self) -> bool {
+        matches!(self.kind, ForeignItemKind::Type)
+    }
+}
+
+// A single extern block declares many items,
+// so this can't go through from_items!
+pub fn foreign_items_from_items(
+    items: &[syn::Item],
+    module: &mut Path,
+    file: &StdPath,
+) -> HashMap<Path, Vec<ForeignItem>> {
+    use syn::Item;
+    let mut things: HashMap<Path, Vec<ForeignItem>> = HashMap::new();
+    for item in items {
+        match item {
+            Item::ForeignMod(item) => {
+                let abi = abi_to_string(&item.abi);
+                let foreign_items = item
+                    .items
+                    .iter()
+                    .filter_map(|item| ForeignItem::from_syn(item, &abi, module.clone(), file));
+                things
+                    .entry(module.clone())
+                    .or_default()
+                    .extend(foreign_items);
+            }
+            Item::Mod(item) => {
+                module.push_name(item.ident.to_string());
+                if let Some((_, content)) = &item.content {
+                    let new_things = foreign_items_from_items(content, module, file);
+                    things.extend(new_things);
+                }
+                module.pop();
+            }
+            _ => {}
+        }
+    }
+    things
+}
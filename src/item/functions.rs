@@ -0,0 +1,294 @@
+use colored::*;
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{from_items, printer::TreePrintable, tree::TreeItem};
+
+use super::{
+    attrs::Attributes,
+    generics::Generics,
+    render::{abi_to_string, return_type_to_string, type_to_string},
+    span::Span,
+    structs::{Path, Visibility},
+};
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    vis: Visibility,
+    sig: Signature,
+    attrs: Attributes,
+    module: Path,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    name: String,
+    generics: Generics,
+    args: Vec<FnArg>,
+    variadic: bool,
+    ret: Option<String>,
+    qualifiers: FnQualifiers,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FnArg {
+    // self, mut self
+    SelfValue {
+        mutable: bool,
+    },
+    // &self, &'a mut self
+    SelfRef {
+        lifetime: Option<String>,
+        mutable: bool,
+    },
+    // name: Type, self: Box<Self>
+    Typed {
+        name: String,
+        ty: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FnQualifiers {
+    is_const: bool,
+    is_async: bool,
+    is_unsafe: bool,
+    abi: Option<String>,
+}
+
+impl Display for FnArg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FnArg::SelfValue { mutable } => {
+                if *mutable {
+                    write!(f, "mut ")?;
+                }
+                write!(f, "self")
+            }
+            FnArg::SelfRef { lifetime, mutable } => {
+                write!(f, "&")?;
+                if let Some(lifetime) = lifetime {
+                    write!(f, "{} ", lifetime)?;
+                }
+                if *mutable {
+                    write!(f, "mut ")?;
+                }
+                write!(f, "self")
+            }
+            FnArg::Typed { name, ty } => write!(f, "{}: {}", name, ty),
+        }
+    }
+}
+
+impl Display for FnQualifiers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_const {
+            write!(f, "const ")?;
+        }
+        if self.is_async {
+            write!(f, "async ")?;
+        }
+        if self.is_unsafe {
+            write!(f, "unsafe ")?;
+        }
+        if let Some(abi) = &self.abi {
+            write!(f, "extern \"{}\" ", abi)?;
+        }
+        Ok(())
+    }
+}
+
+impl Signature {
+    pub(crate) fn from_syn(sig: &syn::Signature) -> Self {
+        let generics = Generics::from_syn(&sig.generics);
+        let args = sig.inputs.iter().map(FnArg::from_syn).collect();
+        let qualifiers = FnQualifiers {
+            is_const: sig.constness.is_some(),
+            is_async: sig.asyncness.is_some(),
+            is_unsafe: sig.unsafety.is_some(),
+            abi: sig.abi.as_ref().map(abi_to_string),
+        };
+        Self {
+            name: sig.ident.to_string(),
+            generics,
+            args,
+            variadic: sig.variadic.is_some(),
+            ret: return_type_to_string(&sig.output),
+            qualifiers,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
+    pub fn args(&self) -> &[FnArg] {
+        &self.args
+    }
+
+    pub fn is_variadic(&self) -> bool {
+        self.variadic
+    }
+
+    pub fn ret(&self) -> Option<&String> {
+        self.ret.as_ref()
+    }
+
+    pub fn qualifiers(&self) -> &FnQualifiers {
+        &self.qualifiers
+    }
+
+    // Writes everything after the name, ie. `<T>(a: A) -> R`
+    pub(crate) fn write_rest(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.generics)?;
+        let mut args: Vec<_> = self.args.iter().map(FnArg::to_string).collect();
+        if self.variadic {
+            args.push(String::from("..."));
+        }
+        write!(f, "({})", args.join(", "))?;
+        if let Some(ret) = &self.ret {
+            write!(f, " -> {}", ret)?;
+        }
+        write!(f, "{}", self.generics.where_clause())
+    }
+}
+
+impl FnArg {
+    fn from_syn(arg: &syn::FnArg) -> Self {
+        match arg {
+            syn::FnArg::Receiver(receiver) => match &receiver.reference {
+                Some((_, lifetime)) => FnArg::SelfRef {
+                    lifetime: lifetime.as_ref().map(|lifetime| lifetime.to_string()),
+                    mutable: receiver.mutability.is_some(),
+                },
+                None => FnArg::SelfValue {
+                    mutable: receiver.mutability.is_some(),
+                },
+            },
+            syn::FnArg::Typed(pat_type) => {
+                let name = match pat_type.pat.as_ref() {
+                    syn::Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+                    _ => String::from("_"),
+                };
+                FnArg::Typed {
+                    name,
+                    ty: type_to_string(&pat_type.ty),
+                }
+            }
+        }
+    }
+
+    pub fn is_receiver(&self) -> bool {
+        match self {
+            FnArg::SelfValue { .. } | FnArg::SelfRef { .. } => true,
+            FnArg::Typed { name, .. } => name == "self",
+        }
+    }
+}
+
+impl FnQualifiers {
+    pub fn is_const(&self) -> bool {
+        self.is_const
+    }
+
+    pub fn is_async(&self) -> bool {
+        self.is_async
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.is_unsafe
+    }
+
+    pub fn abi(&self) -> Option<&String> {
+        self.abi.as_ref()
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{} {}::{}",
+            self.vis.to_string().magenta(),
+            self.sig.qualifiers.to_string().green(),
+            "fn".green(),
+            self.module,
+            self.sig.name.yellow(),
+        )?;
+        self.sig.write_rest(f)
+    }
+}
+
+impl TreeItem for Function {
+    fn module(&self) -> &Path {
+        &self.module
+    }
+
+    fn name(&self) -> &str {
+        &self.sig.name
+    }
+}
+
+impl TreePrintable for Function {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+impl Function {
+    fn from_syn(item: &syn::ItemFn, module: Path, span: Span) -> Self {
+        let vis = Visibility::from_syn(&item.vis);
+        let sig = Signature::from_syn(&item.sig);
+        let attrs = Attributes::from_syn(&item.attrs);
+        Self {
+            vis,
+            sig,
+            attrs,
+            module,
+            span,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.sig
+    }
+
+    pub fn visibility(&self) -> &Visibility {
+        &self.vis
+    }
+
+    pub(crate) fn renamed(&self, new_name: &str) -> Self {
+        let mut sig = self.sig.clone();
+        sig.name = String::from(new_name);
+        Self {
+            vis: self.vis.clone(),
+            sig,
+            attrs: self.attrs.clone(),
+            module: self.module.clone(),
+            span: self.span.clone(),
+        }
+    }
+
+    pub(crate) fn set_visibility(&mut self, vis: Visibility) {
+        self.vis = vis;
+    }
+}
+
+from_items!(functions_from_items, Function, Fn);
@@ -0,0 +1,269 @@
+use std::fmt::{self, Display, Formatter};
+
+use colored::*;
+
+use super::render::{bound_to_string, tokens_to_string, type_to_string};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Generics {
+    params: Vec<GenericParam>,
+    where_predicates: Vec<WherePredicate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenericParam {
+    // 'a: 'b + 'c
+    Lifetime {
+        name: String,
+        bounds: Vec<String>,
+    },
+    // T: Clone = Foo
+    Type {
+        name: String,
+        bounds: Vec<String>,
+        default: Option<String>,
+    },
+    // const N: usize = 4
+    Const {
+        name: String,
+        ty: String,
+        default: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WherePredicate {
+    // for<'a> T: Trait<'a>, 'a: 'b
+    Bound {
+        lifetimes: Vec<String>,
+        bounded: String,
+        bounds: Vec<String>,
+    },
+    // T = U
+    Eq {
+        lhs: String,
+        rhs: String,
+    },
+}
+
+impl Display for GenericParam {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GenericParam::Lifetime { name, bounds } => {
+                write!(f, "{}", name)?;
+                if !bounds.is_empty() {
+                    write!(f, ": {}", bounds.join(" + "))?;
+                }
+                Ok(())
+            }
+            GenericParam::Type {
+                name,
+                bounds,
+                default,
+            } => {
+                write!(f, "{}", name)?;
+                if !bounds.is_empty() {
+                    write!(f, ": {}", bounds.join(" + "))?;
+                }
+                if let Some(default) = default {
+                    write!(f, " = {}", default)?;
+                }
+                Ok(())
+            }
+            GenericParam::Const { name, ty, default } => {
+                write!(f, "const {}: {}", name, ty)?;
+                if let Some(default) = default {
+                    write!(f, " = {}", default)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for WherePredicate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WherePredicate::Bound {
+                lifetimes,
+                bounded,
+                bounds,
+            } => {
+                if !lifetimes.is_empty() {
+                    write!(f, "for<{}> ", lifetimes.join(", "))?;
+                }
+                write!(f, "{}: {}", bounded, bounds.join(" + "))
+            }
+            WherePredicate::Eq { lhs, rhs } => write!(f, "{} = {}", lhs, rhs),
+        }
+    }
+}
+
+// Only writes the parameter list, eg. `<'a, T: Clone>`.
+// The where-clause is written separately by where_clause()
+// as it goes at the end of the item.
+impl Display for Generics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.params.is_empty() {
+            let params: Vec<_> = self.params.iter().map(GenericParam::to_string).collect();
+            write!(f, "<{}>", params.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl GenericParam {
+    fn from_syn(param: &syn::GenericParam) -> Self {
+        match param {
+            syn::GenericParam::Lifetime(def) => GenericParam::Lifetime {
+                name: def.lifetime.to_string(),
+                bounds: def.bounds.iter().map(|bound| bound.to_string()).collect(),
+            },
+            syn::GenericParam::Type(param) => GenericParam::Type {
+                name: param.ident.to_string(),
+                bounds: param.bounds.iter().map(bound_to_string).collect(),
+                default: param.default.as_ref().map(type_to_string),
+            },
+            syn::GenericParam::Const(param) => GenericParam::Const {
+                name: param.ident.to_string(),
+                ty: type_to_string(&param.ty),
+                default: param.default.as_ref().map(tokens_to_string),
+            },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            GenericParam::Lifetime { name, .. } => name,
+            GenericParam::Type { name, .. } => name,
+            GenericParam::Const { name, .. } => name,
+        }
+    }
+}
+
+impl WherePredicate {
+    fn from_syn(pred: &syn::WherePredicate) -> Self {
+        match pred {
+            syn::WherePredicate::Type(pred) => WherePredicate::Bound {
+                lifetimes: pred
+                    .lifetimes
+                    .as_ref()
+                    .map(|lifetimes| {
+                        lifetimes
+                            .lifetimes
+                            .iter()
+                            .map(|def| def.lifetime.to_string())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                bounded: type_to_string(&pred.bounded_ty),
+                bounds: pred.bounds.iter().map(bound_to_string).collect(),
+            },
+            syn::WherePredicate::Lifetime(pred) => WherePredicate::Bound {
+                lifetimes: Vec::new(),
+                bounded: pred.lifetime.to_string(),
+                bounds: pred.bounds.iter().map(|bound| bound.to_string()).collect(),
+            },
+            syn::WherePredicate::Eq(pred) => WherePredicate::Eq {
+                lhs: type_to_string(&pred.lhs_ty),
+                rhs: type_to_string(&pred.rhs_ty),
+            },
+        }
+    }
+}
+
+impl Generics {
+    pub(crate) fn from_syn(generics: &syn::Generics) -> Self {
+        let params = generics.params.iter().map(GenericParam::from_syn).collect();
+        let where_predicates = generics
+            .where_clause
+            .as_ref()
+            .map(|clause| {
+                clause
+                    .predicates
+                    .iter()
+                    .map(WherePredicate::from_syn)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            params,
+            where_predicates,
+        }
+    }
+
+    pub fn params(&self) -> &[GenericParam] {
+        &self.params
+    }
+
+    pub fn where_predicates(&self) -> &[WherePredicate] {
+        &self.where_predicates
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty() && self.where_predicates.is_empty()
+    }
+
+    pub fn lifetimes(&self) -> impl Iterator<Item = &GenericParam> {
+        self.params
+            .iter()
+            .filter(|param| matches!(param, GenericParam::Lifetime { .. }))
+    }
+
+    pub fn type_params(&self) -> impl Iterator<Item = &GenericParam> {
+        self.params
+            .iter()
+            .filter(|param| matches!(param, GenericParam::Type { .. }))
+    }
+
+    pub fn const_params(&self) -> impl Iterator<Item = &GenericParam> {
+        self.params
+            .iter()
+            .filter(|param| matches!(param, GenericParam::Const { .. }))
+    }
+
+    pub fn where_clause(&self) -> WhereClause<'_> {
+        WhereClause(&self.where_predicates)
+    }
+}
+
+// Displays as ` where T: Send, U: Sync`, or nothing if there are no predicates
+pub struct WhereClause<'g>(&'g [WherePredicate]);
+
+impl Display for WhereClause<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.0.is_empty() {
+            let preds: Vec<_> = self.0.iter().map(WherePredicate::to_string).collect();
+            write!(f, " {} {}", "where".green(), preds.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generics_display() {
+        let item: syn::ItemStruct = syn::parse_str(
+            "struct Foo<'a, 'b: 'a, T: Clone + ?Sized = u8, const N: usize = 4> \
+             where T: Send, for<'c> &'c T: IntoIterator { x: &'a T }",
+        )
+        .unwrap();
+        let generics = Generics::from_syn(&item.generics);
+        assert_eq!(
+            generics.to_string(),
+            "<'a, 'b: 'a, T: Clone + ?Sized = u8, const N: usize = 4>"
+        );
+        let preds: Vec<_> = generics
+            .where_predicates()
+            .iter()
+            .map(WherePredicate::to_string)
+            .collect();
+        assert_eq!(preds, vec!["T: Send", "for<'c> &'c T: IntoIterator"]);
+        assert_eq!(generics.lifetimes().count(), 2);
+        assert_eq!(generics.type_params().count(), 1);
+        assert_eq!(generics.const_params().count(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+use cargo::core::PackageId;
+use colored::*;
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{from_items, printer::TreePrintable, use_path::UsePath};
+
+use super::{
+    functions::Signature,
+    generics::Generics,
+    render::{path_to_string, type_to_string},
+    span::Span,
+    structs::{Path, Visibility},
+};
+
+#[derive(Debug, Clone)]
+pub struct Impl {
+    generics: Generics,
+    is_unsafe: bool,
+    is_negative: bool,
+    self_ty: String,
+    self_use_path: Option<UsePath>,
+    self_path: Option<Path>,
+    // The package self_path is in, as crates of
+    // different versions have the same paths
+    self_pkg: Option<PackageId>,
+    trait_name: Option<String>,
+    trait_use_path: Option<UsePath>,
+    trait_path: Option<Path>,
+    trait_pkg: Option<PackageId>,
+    items: Vec<ImplItem>,
+    module: Path,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImplItem {
+    Method {
+        vis: Visibility,
+        sig: Signature,
+    },
+    Const {
+        vis: Visibility,
+        name: String,
+        ty: String,
+    },
+    Type {
+        vis: Visibility,
+        name: String,
+        ty: String,
+    },
+}
+
+impl Display for ImplItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ImplItem::Method { vis, sig } => {
+                write!(
+                    f,
+                    "{}{}{} {}",
+                    vis.to_string().magenta(),
+                    sig.qualifiers().to_string().green(),
+                    "fn".green(),
+                    sig.name().yellow()
+                )?;
+                sig.write_rest(f)
+            }
+            ImplItem::Const { vis, name, ty } => write!(
+                f,
+                "{}{} {}: {}",
+                vis.to_string().magenta(),
+                "const".green(),
+                name.yellow(),
+                ty
+            ),
+            ImplItem::Type { vis, name, ty } => write!(
+                f,
+                "{}{} {} = {}",
+                vis.to_string().magenta(),
+                "type".green(),
+                name.yellow(),
+                ty
+            ),
+        }
+    }
+}
+
+impl TreePrintable for ImplItem {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+impl ImplItem {
+    fn from_syn(item: &syn::ImplItem) -> Option<Self> {
+        match item {
+            syn::ImplItem::Method(m) => Some(ImplItem::Method {
+                vis: Visibility::from_syn(&m.vis),
+                sig: Signature::from_syn(&m.sig),
+            }),
+            syn::ImplItem::Const(c) => Some(ImplItem::Const {
+                vis: Visibility::from_syn(&c.vis),
+                name: c.ident.to_string(),
+                ty: type_to_string(&c.ty),
+            }),
+            syn::ImplItem::Type(ty) => Some(ImplItem::Type {
+                vis: Visibility::from_syn(&ty.vis),
+                name: ty.ident.to_string(),
+                ty: type_to_string(&ty.ty),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            ImplItem::Method { sig, .. } => sig.name(),
+            ImplItem::Const { name, .. } => name,
+            ImplItem::Type { name, .. } => name,
+        }
+    }
+}
+
+impl Display for Impl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_unsafe {
+            write!(f, "{} ", "unsafe".green())?;
+        }
+        write!(f, "{}", "impl".green())?;
+        write!(f, "{}", self.generics)?;
+        if let Some(trait_name) = &self.trait_name {
+            if self.is_negative {
+                write!(f, " !{}", trait_name)?;
+            } else {
+                write!(f, " {}", trait_name)?;
+            }
+            write!(f, " {}", "for".green())?;
+        }
+        write!(f, " {}", self.self_ty.yellow())?;
+        write!(f, "{}", self.generics.where_clause())?;
+        write!(f, " (in {})", self.module)
+    }
+}
+
+impl TreePrintable for Impl {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        self.items
+            .iter()
+            .map(|item| item as &dyn TreePrintable)
+            .collect()
+    }
+}
+
+// Only plain paths (optionally with generic arguments) can be
+// looked up in an ItemTree, so references, slices, trait objects, etc.
+// are left unresolved.
+fn type_use_path(ty: &syn::Type) -> Option<UsePath> {
+    match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => {
+            Some(UsePath::from_syn_path(&type_path.path))
+        }
+        syn::Type::Group(group) => type_use_path(&group.elem),
+        syn::Type::Paren(paren) => type_use_path(&paren.elem),
+        _ => None,
+    }
+}
+
+impl Impl {
+    fn from_syn(item: &syn::ItemImpl, module: Path, span: Span) -> Self {
+        let generics = Generics::from_syn(&item.generics);
+        let (is_negative, trait_name, trait_use_path) = match &item.trait_ {
+            Some((bang, path, _)) => (
+                bang.is_some(),
+                Some(path_to_string(path)),
+                Some(UsePath::from_syn_path(path)),
+            ),
+            None => (false, None, None),
+        };
+        Self {
+            generics,
+            is_unsafe: item.unsafety.is_some(),
+            is_negative,
+            self_ty: type_to_string(&item.self_ty),
+            self_use_path: type_use_path(&item.self_ty),
+            self_path: None,
+            self_pkg: None,
+            trait_name,
+            trait_use_path,
+            trait_path: None,
+            trait_pkg: None,
+            items: item.items.iter().filter_map(ImplItem::from_syn).collect(),
+            module,
+            span,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn module(&self) -> &Path {
+        &self.module
+    }
+
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
+    pub fn self_ty(&self) -> &str {
+        &self.self_ty
+    }
+
+    // Path to the definition of the self type, if it could be resolved
+    pub fn self_path(&self) -> Option<&Path> {
+        self.self_path.as_ref()
+    }
+
+    pub fn self_package(&self) -> Option<PackageId> {
+        self.self_pkg
+    }
+
+    pub fn trait_name(&self) -> Option<&String> {
+        self.trait_name.as_ref()
+    }
+
+    // Path to the definition of the implemented trait, if it could be resolved
+    pub fn trait_path(&self) -> Option<&Path> {
+        self.trait_path.as_ref()
+    }
+
+    pub fn trait_package(&self) -> Option<PackageId> {
+        self.trait_pkg
+    }
+
+    pub fn is_inherent(&self) -> bool {
+        self.trait_name.is_none()
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.is_unsafe
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.is_negative
+    }
+
+    pub fn items(&self) -> &[ImplItem] {
+        &self.items
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = (&Visibility, &Signature)> {
+        self.items.iter().filter_map(|item| match item {
+            ImplItem::Method { vis, sig } => Some((vis, sig)),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn self_use_path(&self) -> Option<&UsePath> {
+        self.self_use_path.as_ref()
+    }
+
+    pub(crate) fn trait_use_path(&self) -> Option<&UsePath> {
+        self.trait_use_path.as_ref()
+    }
+
+    pub(crate) fn set_self_path(&mut self, path: Path, pkg: Option<PackageId>) {
+        self.self_path = Some(path);
+        self.self_pkg = pkg;
+    }
+
+    pub(crate) fn set_trait_path(&mut self, path: Path, pkg: Option<PackageId>) {
+        self.trait_path = Some(path);
+        self.trait_pkg = pkg;
+    }
+}
+
+from_items!(impls_from_items, Impl, Impl);
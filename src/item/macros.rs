@@ -0,0 +1,247 @@
+use colored::*;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    path::Path as StdPath,
+};
+
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+
+use crate::{printer::TreePrintable, tree::TreeItem};
+
+use super::{attrs::Attributes, span::Span, structs::Path};
+
+#[derive(Debug, Clone)]
+pub struct Macro {
+    name: String,
+    exported: bool,
+    rules: usize,
+    attrs: Attributes,
+    defining_module: Path,
+    // Where the macro can be named from. This is the crate root for
+    // #[macro_export] macros, otherwise the outermost module its
+    // textual scope reaches.
+    module: Path,
+    span: Span,
+}
+
+impl Display for Macro {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.exported {
+            write!(f, "{} ", "#[macro_export]".magenta())?;
+        }
+        write!(
+            f,
+            "{} {}::{}",
+            "macro_rules!".green(),
+            self.module,
+            self.name.yellow()
+        )?;
+        let plural = if self.rules == 1 { "" } else { "s" };
+        write!(f, " ({} rule{})", self.rules, plural)?;
+        if self.defining_module != self.module {
+            write!(f, " (defined in {})", self.defining_module)?;
+        }
+        Ok(())
+    }
+}
+
+impl TreeItem for Macro {
+    fn module(&self) -> &Path {
+        &self.module
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TreePrintable for Macro {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+// Each rule is `(matcher) => {transcriber}`, so counting the
+// top-level `=>` gives the number of matchers.
+fn count_rules(tokens: &TokenStream) -> usize {
+    let mut count = 0;
+    let mut prev_eq = false;
+    for tt in tokens.clone() {
+        prev_eq = match tt {
+            TokenTree::Punct(punct) => {
+                if punct.as_char() == '>' && prev_eq {
+                    count += 1;
+                }
+                punct.as_char() == '=' && punct.spacing() == Spacing::Joint
+            }
+            _ => false,
+        };
+    }
+    count
+}
+
+fn has_attr(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path.is_ident(name))
+}
+
+impl Macro {
+    fn from_syn(item: &syn::ItemMacro, module: Path, span: Span) -> Option<Self> {
+        if !item.mac.path.is_ident("macro_rules") {
+            return None;
+        }
+        let name = item.ident.as_ref()?.to_string();
+        let exported = has_attr(&item.attrs, "macro_export");
+        let scope = if exported {
+            module.first_as_path()
+        } else {
+            module.clone()
+        };
+        Some(Self {
+            name,
+            exported,
+            rules: count_rules(&item.mac.tokens),
+            attrs: Attributes::from_syn(&item.attrs),
+            defining_module: module,
+            module: scope,
+            span,
+        })
+    }
+
+    pub fn is_exported(&self) -> bool {
+        self.exported
+    }
+
+    pub fn rules(&self) -> usize {
+        self.rules
+    }
+
+    pub fn defining_module(&self) -> &Path {
+        &self.defining_module
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    // A macro defined in a #[macro_use] module stays in scope after the
+    // module ends, so it is visible from the parent, and transitively
+    // from the grandparent if the parent is #[macro_use] too.
+    pub(crate) fn hoist(&mut self, macro_use_modules: &HashSet<Path>) {
+        if self.exported {
+            return;
+        }
+        while macro_use_modules.contains(&self.module) {
+            self.module = self.module.parent();
+        }
+    }
+}
+
+pub fn macros_from_items(
+    items: &[syn::Item],
+    module: &mut Path,
+    file: &StdPath,
+) -> HashMap<Path, Vec<Macro>> {
+    use syn::Item;
+
+    let mut macros: HashMap<Path, Vec<Macro>> = HashMap::new();
+    for item in items {
+        match item {
+            Item::Macro(item) => {
+                if let Some(mac) = Macro::from_syn(item, module.clone(), Span::from_syn(item, file))
+                {
+                    macros.entry(module.clone()).or_default().push(mac);
+                }
+            }
+            Item::Mod(item) => {
+                module.push_name(item.ident.to_string());
+                if let Some((_, content)) = &item.content {
+                    macros.extend(macros_from_items(content, module, file));
+                }
+                module.pop();
+            }
+            _ => {}
+        }
+    }
+    macros
+}
+
+// Paths of the modules declared with #[macro_use], keyed by the module
+// containing the declaration. Since the attribute sits on the `mod`
+// item, this also covers modules whose contents are in another file.
+pub fn macro_use_modules_from_items(
+    items: &[syn::Item],
+    module: &mut Path,
+    file: &StdPath,
+) -> HashMap<Path, Vec<Path>> {
+    use syn::Item;
+
+    let mut modules: HashMap<Path, Vec<Path>> = HashMap::new();
+    for item in items {
+        if let Item::Mod(item) = item {
+            let parent = module.clone();
+            module.push_name(item.ident.to_string());
+            if has_attr(&item.attrs, "macro_use") {
+                modules.entry(parent).or_default().push(module.clone());
+            }
+            if let Some((_, content)) = &item.content {
+                modules.extend(macro_use_modules_from_items(content, module, file));
+            }
+            module.pop();
+        }
+    }
+    modules
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_macro_scope() {
+        let file: syn::File = syn::parse_str(
+            "#[macro_use] mod inner { \
+                 macro_rules! local { () => {}; ($e:expr) => { $e }; } \
+                 mod deep { #[macro_export] macro_rules! exported { ($($t:tt)*) => {} } } \
+             }",
+        )
+        .unwrap();
+        let mut module = Path::from(vec!["krate"]);
+        let macros: Vec<_> = macros_from_items(&file.items, &mut module, StdPath::new("lib.rs"))
+            .into_values()
+            .flatten()
+            .collect();
+        let macro_use_modules: HashSet<_> =
+            macro_use_modules_from_items(&file.items, &mut module, StdPath::new("lib.rs"))
+                .into_values()
+                .flatten()
+                .collect();
+        assert_eq!(macros.len(), 2);
+        for mut mac in macros {
+            mac.hoist(&macro_use_modules);
+            match mac.name() {
+                "local" => {
+                    assert_eq!(mac.rules(), 2);
+                    assert_eq!(mac.span().start().0, 1);
+                    assert_eq!(mac.module(), &Path::from(vec!["krate"]));
+                    assert_eq!(mac.defining_module(), &Path::from(vec!["krate", "inner"]));
+                }
+                "exported" => {
+                    assert!(mac.is_exported());
+                    assert_eq!(mac.rules(), 1);
+                    assert_eq!(mac.module(), &Path::from(vec!["krate"]));
+                }
+                name => panic!("unexpected macro {}", name),
+            }
+        }
+    }
+}
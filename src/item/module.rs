@@ -1,30 +1,43 @@
 use std::{
     collections::HashMap,
     fmt::{self, Display, Formatter},
+    path::Path as StdPath,
 };
 
 use crate::{printer::TreePrintable, tree::TreeItem};
 use colored::*;
 
-use super::structs::Path;
+use super::{attrs::Attributes, span::Span, structs::Path};
 
 #[derive(Clone)]
 pub struct Module {
     path: Path,
     name: String,
     parent: Path,
+    attrs: Attributes,
+    span: Span,
 }
 
 impl Module {
-    pub fn new(names: &[String]) -> Self {
+    pub fn new(names: &[String], span: Span) -> Self {
         let path = Path::from(names.to_vec());
         let parent = path.parent();
         Self {
             path,
             name: names.last().unwrap().clone(),
             parent,
+            attrs: Attributes::default(),
+            span,
         }
     }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
 }
 
 impl Display for Module {
@@ -53,41 +66,52 @@ impl TreeItem for Module {
     }
 }
 
-pub fn modules_from_items(items: &[syn::Item], module: &mut Path) -> HashMap<Path, Vec<Module>> {
-    use syn::Item;
+// attrs are those of the file, including the
+// ones on its `mod` declaration
+pub fn modules_from_items(
+    attrs: &Attributes,
+    items: &[syn::Item],
+    module: &mut Path,
+    file: &StdPath,
+) -> HashMap<Path, Vec<Module>> {
     let mut modules: HashMap<Path, Vec<Module>> = HashMap::new();
     let current_module = Module {
         path: module.clone(),
         parent: module.parent(),
         name: module.components().last().unwrap().to_string(),
+        attrs: attrs.clone(),
+        span: Span::from_file(items, file),
     };
-    if let Some(existing_modules) = modules.get_mut(&current_module.parent) {
-        existing_modules.push(current_module);
-    } else {
-        modules.insert(current_module.parent.clone(), vec![current_module]);
-    }
+    modules.insert(current_module.parent.clone(), vec![current_module]);
+    inline_modules_from_items(items, module, file, &mut modules);
+    modules
+}
+
+// Kept apart from modules_from_items so that an inline module is only
+// added once, with the attributes and span of its `mod` item.
+fn inline_modules_from_items(
+    items: &[syn::Item],
+    module: &mut Path,
+    file: &StdPath,
+    modules: &mut HashMap<Path, Vec<Module>>,
+) {
+    use syn::Item;
     for item in items {
         if let Item::Mod(item) = item {
-            if item.content.is_some() {
+            if let Some((_, content)) = &item.content {
                 let parent = module.clone();
                 module.push_name(item.ident.to_string());
                 let new_module = Module {
                     path: module.clone(),
                     parent: parent.clone(),
                     name: item.ident.to_string(),
+                    attrs: Attributes::from_syn(&item.attrs),
+                    span: Span::from_syn(item, file),
                 };
-                if let Some(existing_modules) = modules.get_mut(&parent) {
-                    existing_modules.push(new_module);
-                } else {
-                    modules.insert(parent, vec![new_module]);
-                }
-                if let Some((_, content)) = &item.content {
-                    let new_modules = modules_from_items(content, module);
-                    modules.extend(new_modules);
-                }
+                modules.entry(parent).or_default().push(new_module);
+                inline_modules_from_items(content, module, file, modules);
                 module.pop();
             }
         }
     }
-    modules
 }
@@ -0,0 +1,179 @@
+use colored::*;
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    path::Path as StdPath,
+};
+
+use syn::{Meta, NestedMeta};
+
+use crate::{printer::TreePrintable, tree::TreeItem};
+
+use super::{attrs::Attributes, span::Span, structs::Path};
+
+#[derive(Debug, Clone)]
+pub struct ProcMacro {
+    // The name the macro is invoked by, which for derives
+    // is not the name of the function implementing it
+    name: String,
+    function: String,
+    kind: ProcMacroKind,
+    helper_attributes: Vec<String>,
+    attrs: Attributes,
+    module: Path,
+    span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcMacroKind {
+    // #[proc_macro]
+    Bang,
+    // #[proc_macro_derive(Name, attributes(helper))]
+    Derive,
+    // #[proc_macro_attribute]
+    Attribute,
+}
+
+impl Display for ProcMacroKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcMacroKind::Bang => write!(f, "proc_macro"),
+            ProcMacroKind::Derive => write!(f, "proc_macro_derive"),
+            ProcMacroKind::Attribute => write!(f, "proc_macro_attribute"),
+        }
+    }
+}
+
+impl Display for ProcMacro {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}::{}",
+            self.kind.to_string().green(),
+            self.module,
+            self.name.yellow()
+        )?;
+        if !self.helper_attributes.is_empty() {
+            write!(f, " (attributes: {})", self.helper_attributes.join(", "))?;
+        }
+        if self.function != self.name {
+            write!(f, " (fn {})", self.function)?;
+        }
+        Ok(())
+    }
+}
+
+impl TreeItem for ProcMacro {
+    fn module(&self) -> &Path {
+        &self.module
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TreePrintable for ProcMacro {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+fn meta_path_name(nested: &NestedMeta) -> Option<String> {
+    match nested {
+        NestedMeta::Meta(Meta::Path(path)) => path.get_ident().map(|ident| ident.to_string()),
+        _ => None,
+    }
+}
+
+impl ProcMacro {
+    fn from_syn(item: &syn::ItemFn, module: Path, span: Span) -> Option<Self> {
+        let function = item.sig.ident.to_string();
+        let mut helper_attributes = Vec::new();
+        let (name, kind) = item.attrs.iter().find_map(|attr| {
+            if attr.path.is_ident("proc_macro") {
+                Some((function.clone(), ProcMacroKind::Bang))
+            } else if attr.path.is_ident("proc_macro_attribute") {
+                Some((function.clone(), ProcMacroKind::Attribute))
+            } else if attr.path.is_ident("proc_macro_derive") {
+                let list = match attr.parse_meta() {
+                    Ok(Meta::List(list)) => list,
+                    _ => return None,
+                };
+                let mut nested = list.nested.iter();
+                let name = nested.next().and_then(meta_path_name)?;
+                for rest in nested {
+                    if let NestedMeta::Meta(Meta::List(attrs)) = rest {
+                        if attrs.path.is_ident("attributes") {
+                            helper_attributes
+                                .extend(attrs.nested.iter().filter_map(meta_path_name));
+                        }
+                    }
+                }
+                Some((name, ProcMacroKind::Derive))
+            } else {
+                None
+            }
+        })?;
+        Some(Self {
+            name,
+            function,
+            kind,
+            helper_attributes,
+            attrs: Attributes::from_syn(&item.attrs),
+            module,
+            span,
+        })
+    }
+
+    pub fn kind(&self) -> ProcMacroKind {
+        self.kind
+    }
+
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn helper_attributes(&self) -> &[String] {
+        &self.helper_attributes
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+// Proc-macros have to be defined at the crate root, so unlike the
+// other extractors this does not descend into modules.
+pub fn proc_macros_from_items(
+    items: &[syn::Item],
+    module: &mut Path,
+    file: &StdPath,
+) -> HashMap<Path, Vec<ProcMacro>> {
+    if module.components().len() != 1 {
+        return HashMap::new();
+    }
+    let proc_macros: Vec<_> = items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Fn(item) => {
+                ProcMacro::from_syn(item, module.clone(), Span::from_syn(item, file))
+            }
+            _ => None,
+        })
+        .collect();
+    let mut things = HashMap::new();
+    if !proc_macros.is_empty() {
+        things.insert(module.clone(), proc_macros);
+    }
+    things
+}
@@ -2,7 +2,7 @@ use std::fmt::{self, Display, Formatter};
 
 use crate::{printer::TreePrintable, tree::TreeItem, use_path::UsePath};
 
-use super::{structs::Path, Item};
+use super::{span::Span, structs::Path, Item};
 
 pub struct ReExport {
     module: Path,
@@ -21,6 +21,19 @@ impl ReExport {
             name,
         }
     }
+
+    pub fn use_path(&self) -> &UsePath {
+        &self.use_path
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    // The span of the `use` item doing the re-export
+    pub fn span(&self) -> Option<&Span> {
+        self.use_path.span()
+    }
 }
 
 impl Display for ReExport {
@@ -0,0 +1,209 @@
+use quote::ToTokens;
+use syn::{GenericArgument, PathArguments, ReturnType, Type, TypeParamBound};
+
+// Renders a type the way it is usually written in source,
+// eg. `&'a mut Vec<u8>` instead of the spaced-out token stream.
+pub(crate) fn type_to_string(ty: &Type) -> String {
+    match ty {
+        Type::Array(arr) => format!(
+            "[{}; {}]",
+            type_to_string(&arr.elem),
+            tokens_to_string(&arr.len)
+        ),
+        Type::BareFn(bare_fn) => {
+            let mut s = String::new();
+            if let Some(lifetimes) = &bare_fn.lifetimes {
+                let lifetimes: Vec<_> = lifetimes
+                    .lifetimes
+                    .iter()
+                    .map(|def| def.lifetime.to_string())
+                    .collect();
+                s.push_str(&format!("for<{}> ", lifetimes.join(", ")));
+            }
+            if bare_fn.unsafety.is_some() {
+                s.push_str("unsafe ");
+            }
+            if let Some(abi) = &bare_fn.abi {
+                s.push_str(&format!("extern \"{}\" ", abi_to_string(abi)));
+            }
+            let mut inputs: Vec<_> = bare_fn
+                .inputs
+                .iter()
+                .map(|arg| match &arg.name {
+                    Some((name, _)) => format!("{}: {}", name, type_to_string(&arg.ty)),
+                    None => type_to_string(&arg.ty),
+                })
+                .collect();
+            if bare_fn.variadic.is_some() {
+                inputs.push(String::from("..."));
+            }
+            s.push_str(&format!("fn({})", inputs.join(", ")));
+            if let Some(output) = return_type_to_string(&bare_fn.output) {
+                s.push_str(&format!(" -> {}", output));
+            }
+            s
+        }
+        Type::Group(group) => type_to_string(&group.elem),
+        Type::ImplTrait(impl_trait) => format!("impl {}", bounds_to_string(&impl_trait.bounds)),
+        Type::Infer(_) => String::from("_"),
+        Type::Never(_) => String::from("!"),
+        Type::Paren(paren) => format!("({})", type_to_string(&paren.elem)),
+        Type::Path(type_path) => match &type_path.qself {
+            Some(qself) => {
+                let segments: Vec<_> = type_path
+                    .path
+                    .segments
+                    .iter()
+                    .map(path_segment_to_string)
+                    .collect();
+                let (trait_segs, rest) = segments.split_at(qself.position);
+                let mut s = format!("<{}", type_to_string(&qself.ty));
+                if !trait_segs.is_empty() {
+                    let global = if type_path.path.leading_colon.is_some() {
+                        "::"
+                    } else {
+                        ""
+                    };
+                    s.push_str(&format!(" as {}{}", global, trait_segs.join("::")));
+                }
+                s.push('>');
+                for seg in rest {
+                    s.push_str("::");
+                    s.push_str(seg);
+                }
+                s
+            }
+            None => path_to_string(&type_path.path),
+        },
+        Type::Ptr(ptr) => {
+            let kind = if ptr.mutability.is_some() {
+                "mut"
+            } else {
+                "const"
+            };
+            format!("*{} {}", kind, type_to_string(&ptr.elem))
+        }
+        Type::Reference(reference) => {
+            let mut s = String::from("&");
+            if let Some(lifetime) = &reference.lifetime {
+                s.push_str(&format!("{} ", lifetime));
+            }
+            if reference.mutability.is_some() {
+                s.push_str("mut ");
+            }
+            s.push_str(&type_to_string(&reference.elem));
+            s
+        }
+        Type::Slice(slice) => format!("[{}]", type_to_string(&slice.elem)),
+        Type::TraitObject(trait_object) => {
+            let bounds = bounds_to_string(&trait_object.bounds);
+            if trait_object.dyn_token.is_some() {
+                format!("dyn {}", bounds)
+            } else {
+                bounds
+            }
+        }
+        Type::Tuple(tuple) => {
+            let elems: Vec<_> = tuple.elems.iter().map(type_to_string).collect();
+            if elems.len() == 1 {
+                format!("({},)", elems[0])
+            } else {
+                format!("({})", elems.join(", "))
+            }
+        }
+        _ => tokens_to_string(ty),
+    }
+}
+
+pub(crate) fn path_to_string(path: &syn::Path) -> String {
+    let segments: Vec<_> = path.segments.iter().map(path_segment_to_string).collect();
+    if path.leading_colon.is_some() {
+        format!("::{}", segments.join("::"))
+    } else {
+        segments.join("::")
+    }
+}
+
+fn path_segment_to_string(seg: &syn::PathSegment) -> String {
+    let name = seg.ident.to_string();
+    match &seg.arguments {
+        PathArguments::None => name,
+        PathArguments::AngleBracketed(args) => {
+            let args: Vec<_> = args.args.iter().map(generic_argument_to_string).collect();
+            format!("{}<{}>", name, args.join(", "))
+        }
+        PathArguments::Parenthesized(args) => {
+            let inputs: Vec<_> = args.inputs.iter().map(type_to_string).collect();
+            let mut s = format!("{}({})", name, inputs.join(", "));
+            if let Some(output) = return_type_to_string(&args.output) {
+                s.push_str(&format!(" -> {}", output));
+            }
+            s
+        }
+    }
+}
+
+fn generic_argument_to_string(arg: &GenericArgument) -> String {
+    match arg {
+        GenericArgument::Lifetime(lifetime) => lifetime.to_string(),
+        GenericArgument::Type(ty) => type_to_string(ty),
+        GenericArgument::Const(expr) => tokens_to_string(expr),
+        GenericArgument::Binding(binding) => {
+            format!("{} = {}", binding.ident, type_to_string(&binding.ty))
+        }
+        GenericArgument::Constraint(constraint) => format!(
+            "{}: {}",
+            constraint.ident,
+            bounds_to_string(&constraint.bounds)
+        ),
+    }
+}
+
+pub(crate) fn bound_to_string(bound: &TypeParamBound) -> String {
+    match bound {
+        TypeParamBound::Trait(trait_bound) => {
+            let mut s = String::new();
+            if let Some(lifetimes) = &trait_bound.lifetimes {
+                let lifetimes: Vec<_> = lifetimes
+                    .lifetimes
+                    .iter()
+                    .map(|def| def.lifetime.to_string())
+                    .collect();
+                s.push_str(&format!("for<{}> ", lifetimes.join(", ")));
+            }
+            if let syn::TraitBoundModifier::Maybe(_) = trait_bound.modifier {
+                s.push('?');
+            }
+            s.push_str(&path_to_string(&trait_bound.path));
+            s
+        }
+        TypeParamBound::Lifetime(lifetime) => lifetime.to_string(),
+    }
+}
+
+pub(crate) fn bounds_to_string<'a, I>(bounds: I) -> String
+where
+    I: IntoIterator<Item = &'a TypeParamBound>,
+{
+    let bounds: Vec<_> = bounds.into_iter().map(bound_to_string).collect();
+    bounds.join(" + ")
+}
+
+pub(crate) fn return_type_to_string(ret: &ReturnType) -> Option<String> {
+    match ret {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => Some(type_to_string(ty)),
+    }
+}
+
+// An `extern` without an explicit ABI string means "C".
+pub(crate) fn abi_to_string(abi: &syn::Abi) -> String {
+    abi.name
+        .as_ref()
+        .map_or_else(|| String::from("C"), |name| name.value())
+}
+
+// Fallback for syntax we do not care to pretty-print (expressions, macros).
+pub(crate) fn tokens_to_string<T: ToTokens>(tokens: &T) -> String {
+    tokens.to_token_stream().to_string()
+}
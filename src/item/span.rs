@@ -0,0 +1,68 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path as StdPath, PathBuf},
+};
+
+use syn::spanned::Spanned;
+
+// Where an item is written in the source. Lines start from 1,
+// columns from 0, as reported by proc-macro2.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    file: PathBuf,
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
+impl Span {
+    pub(crate) fn from_syn<T: Spanned>(node: &T, file: &StdPath) -> Self {
+        let span = node.span();
+        let (start, end) = (span.start(), span.end());
+        Self {
+            file: file.to_path_buf(),
+            start: (start.line, start.column),
+            end: (end.line, end.column),
+        }
+    }
+
+    // Covers all of items, for modules which are a file of their own
+    pub(crate) fn from_file(items: &[syn::Item], file: &StdPath) -> Self {
+        let start = items
+            .first()
+            .map_or((1, 0), |item| Self::from_syn(item, file).start);
+        let end = items
+            .last()
+            .map_or((1, 0), |item| Self::from_syn(item, file).end);
+        Self {
+            file: file.to_path_buf(),
+            start,
+            end,
+        }
+    }
+
+    pub fn file(&self) -> &StdPath {
+        &self.file
+    }
+
+    // (line, column)
+    pub fn start(&self) -> (usize, usize) {
+        self.start
+    }
+
+    // (line, column)
+    pub fn end(&self) -> (usize, usize) {
+        self.end
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.file.display(),
+            self.start.0,
+            self.start.1 + 1
+        )
+    }
+}
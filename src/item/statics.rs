@@ -0,0 +1,93 @@
+use colored::*;
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{from_items, printer::TreePrintable, tree::TreeItem};
+
+use super::{
+    attrs::Attributes,
+    render::type_to_string,
+    span::Span,
+    structs::{Path, Visibility},
+};
+
+#[derive(Debug, Clone)]
+pub struct Static {
+    name: String,
+    vis: Visibility,
+    mutable: bool,
+    ty: String,
+    attrs: Attributes,
+    module: Path,
+    span: Span,
+}
+
+impl Display for Static {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mutability = if self.mutable { " mut" } else { "" };
+        write!(
+            f,
+            "{}{}{} {}::{}: {}",
+            self.vis.to_string().magenta(),
+            "static".green(),
+            mutability.green(),
+            self.module,
+            self.name.yellow(),
+            self.ty
+        )
+    }
+}
+
+impl TreeItem for Static {
+    fn module(&self) -> &Path {
+        &self.module
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TreePrintable for Static {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+impl Static {
+    fn from_syn(item: &syn::ItemStatic, module: Path, span: Span) -> Self {
+        let name = item.ident.to_string();
+        let vis = Visibility::from_syn(&item.vis);
+        Self {
+            name,
+            vis,
+            mutable: item.mutability.is_some(),
+            ty: type_to_string(&item.ty),
+            attrs: Attributes::from_syn(&item.attrs),
+            module,
+            span,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn is_mut(&self) -> bool {
+        self.mutable
+    }
+
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+}
+
+from_items!(statics_from_items, Static, Static);
@@ -4,12 +4,117 @@ use std::fmt::{self, Display, Formatter};
 
 use crate::{from_items, printer::TreePrintable, tree::TreeItem};
 
+use super::{attrs::Attributes, generics::Generics, render::type_to_string, span::Span};
+
 #[derive(Debug, Clone)]
 pub struct Struct {
     name: String,
     vis: Visibility,
-    params: Vec<String>,
+    generics: Generics,
+    fields: Fields,
+    attrs: Attributes,
     module: Path,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fields {
+    Named(Vec<Field>), // struct Foo { a: A }
+    Tuple(Vec<Field>), // struct Foo(A);
+    Unit,              // struct Foo;
+}
+
+// Tuple fields are named by their index, as in `foo.0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    name: String,
+    vis: Visibility,
+    ty: String,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}: {}",
+            self.vis.to_string().magenta(),
+            self.name.yellow(),
+            self.ty
+        )
+    }
+}
+
+impl TreePrintable for Field {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+impl Field {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn visibility(&self) -> &Visibility {
+        &self.vis
+    }
+
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+}
+
+impl Fields {
+    pub(crate) fn from_syn(fields: &syn::Fields) -> Self {
+        match fields {
+            syn::Fields::Named(named) => Self::from_syn_named(named),
+            syn::Fields::Unnamed(unnamed) => Fields::Tuple(
+                unnamed
+                    .unnamed
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, field)| Field {
+                        name: idx.to_string(),
+                        vis: Visibility::from_syn(&field.vis),
+                        ty: type_to_string(&field.ty),
+                    })
+                    .collect(),
+            ),
+            syn::Fields::Unit => Fields::Unit,
+        }
+    }
+
+    pub(crate) fn from_syn_named(named: &syn::FieldsNamed) -> Self {
+        Fields::Named(
+            named
+                .named
+                .iter()
+                .map(|field| Field {
+                    name: field.ident.as_ref().unwrap().to_string(),
+                    vis: Visibility::from_syn(&field.vis),
+                    ty: type_to_string(&field.ty),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        match self {
+            Fields::Named(fields) | Fields::Tuple(fields) => fields,
+            Fields::Unit => &[],
+        }
+    }
+
+    pub(crate) fn tree_children(&self) -> Vec<&dyn TreePrintable> {
+        self.fields()
+            .iter()
+            .map(|field| field as &dyn TreePrintable)
+            .collect()
+    }
 }
 
 impl Display for Struct {
@@ -22,10 +127,7 @@ impl Display for Struct {
             self.module,
             self.name.yellow(),
         )?;
-        if !self.params.is_empty() {
-            write!(f, "<{}>", self.params.join(","))?;
-        }
-        Ok(())
+        write!(f, "{}{}", self.generics, self.generics.where_clause())
     }
 }
 
@@ -45,7 +147,7 @@ impl TreePrintable for Struct {
     }
 
     fn children(&self) -> Vec<&dyn TreePrintable> {
-        Vec::new()
+        self.fields.tree_children()
     }
 }
 
@@ -123,6 +225,15 @@ impl Path {
     pub fn first_as_path(&self) -> Path {
         Path(vec![self.components().first().unwrap().clone()])
     }
+
+    // The same path in a crate named root
+    pub(crate) fn rerooted(&self, root: &str) -> Path {
+        let mut comps = self.0.clone();
+        if let Some(first) = comps.first_mut() {
+            *first = PathComponent::Name(String::from(root));
+        }
+        Path(comps)
+    }
 }
 
 impl From<Vec<String>> for Path {
@@ -164,28 +275,47 @@ impl Display for Visibility {
 }
 
 impl Struct {
-    fn from_syn(item: &syn::ItemStruct, module: Path) -> Self {
+    fn from_syn(item: &syn::ItemStruct, module: Path, span: Span) -> Self {
         let name = item.ident.to_string();
         let vis = Visibility::from_syn(&item.vis);
-        let params: Vec<String> = item
-            .generics
-            .type_params()
-            .map(|param| param.ident.to_string())
-            .collect();
+        let generics = Generics::from_syn(&item.generics);
+        let fields = Fields::from_syn(&item.fields);
         Self {
             name,
             vis,
-            params,
+            generics,
+            fields,
+            attrs: Attributes::from_syn(&item.attrs),
             module,
+            span,
         }
     }
 
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
     pub(crate) fn renamed(&self, new_name: &str) -> Self {
         Self {
             name: String::from(new_name),
             vis: self.vis.clone(),
-            params: self.params.clone(),
+            generics: self.generics.clone(),
+            fields: self.fields.clone(),
+            attrs: self.attrs.clone(),
             module: self.module.clone(),
+            span: self.span.clone(),
         }
     }
 
@@ -0,0 +1,290 @@
+use colored::*;
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{from_items, printer::TreePrintable, tree::TreeItem};
+
+use super::{
+    attrs::Attributes,
+    functions::Signature,
+    generics::Generics,
+    render::{bound_to_string, type_to_string},
+    span::Span,
+    structs::{Path, Visibility},
+};
+
+#[derive(Debug, Clone)]
+pub struct Trait {
+    name: String,
+    vis: Visibility,
+    generics: Generics,
+    supertraits: Vec<String>,
+    is_unsafe: bool,
+    is_auto: bool,
+    items: Vec<AssocItem>,
+    attrs: Attributes,
+    module: Path,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssocItem {
+    Type(AssocType),
+    Const(AssocConst),
+    Method(TraitMethod),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssocType {
+    name: String,
+    bounds: Vec<String>,
+    default: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssocConst {
+    name: String,
+    ty: String,
+    has_default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitMethod {
+    sig: Signature,
+    has_default: bool,
+}
+
+impl Display for AssocType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", "type".green(), self.name.yellow())?;
+        if !self.bounds.is_empty() {
+            write!(f, ": {}", self.bounds.join(" + "))?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " = {}", default)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for AssocConst {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", "const".green(), self.name.yellow(), self.ty)?;
+        if self.has_default {
+            write!(f, " {}", "(provided)".cyan())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TraitMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {}",
+            self.sig.qualifiers().to_string().green(),
+            "fn".green(),
+            self.sig.name().yellow()
+        )?;
+        self.sig.write_rest(f)?;
+        if self.has_default {
+            write!(f, " {}", "(provided)".cyan())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for AssocItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AssocItem::Type(ty) => write!(f, "{}", ty),
+            AssocItem::Const(c) => write!(f, "{}", c),
+            AssocItem::Method(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl TreePrintable for AssocItem {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        Vec::new()
+    }
+}
+
+impl AssocItem {
+    fn from_syn(item: &syn::TraitItem) -> Option<Self> {
+        match item {
+            syn::TraitItem::Type(ty) => Some(AssocItem::Type(AssocType {
+                name: ty.ident.to_string(),
+                bounds: ty.bounds.iter().map(bound_to_string).collect(),
+                default: ty.default.as_ref().map(|(_, ty)| type_to_string(ty)),
+            })),
+            syn::TraitItem::Const(c) => Some(AssocItem::Const(AssocConst {
+                name: c.ident.to_string(),
+                ty: type_to_string(&c.ty),
+                has_default: c.default.is_some(),
+            })),
+            syn::TraitItem::Method(m) => Some(AssocItem::Method(TraitMethod {
+                sig: Signature::from_syn(&m.sig),
+                has_default: m.default.is_some(),
+            })),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            AssocItem::Type(ty) => &ty.name,
+            AssocItem::Const(c) => &c.name,
+            AssocItem::Method(m) => m.sig.name(),
+        }
+    }
+
+    // Whether implementors get this item for free
+    pub fn has_default(&self) -> bool {
+        match self {
+            AssocItem::Type(ty) => ty.default.is_some(),
+            AssocItem::Const(c) => c.has_default,
+            AssocItem::Method(m) => m.has_default,
+        }
+    }
+}
+
+impl AssocType {
+    pub fn bounds(&self) -> &[String] {
+        &self.bounds
+    }
+
+    pub fn default(&self) -> Option<&String> {
+        self.default.as_ref()
+    }
+}
+
+impl AssocConst {
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+}
+
+impl TraitMethod {
+    pub fn signature(&self) -> &Signature {
+        &self.sig
+    }
+}
+
+impl Display for Trait {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.vis.to_string().magenta())?;
+        if self.is_unsafe {
+            write!(f, "{} ", "unsafe".green())?;
+        }
+        if self.is_auto {
+            write!(f, "{} ", "auto".green())?;
+        }
+        write!(
+            f,
+            "{} {}::{}",
+            "trait".green(),
+            self.module,
+            self.name.yellow(),
+        )?;
+        write!(f, "{}", self.generics)?;
+        if !self.supertraits.is_empty() {
+            write!(f, ": {}", self.supertraits.join(" + "))?;
+        }
+        write!(f, "{}", self.generics.where_clause())
+    }
+}
+
+impl TreeItem for Trait {
+    fn module(&self) -> &Path {
+        &self.module
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TreePrintable for Trait {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        self.items
+            .iter()
+            .map(|item| item as &dyn TreePrintable)
+            .collect()
+    }
+}
+
+impl Trait {
+    fn from_syn(item: &syn::ItemTrait, module: Path, span: Span) -> Self {
+        let name = item.ident.to_string();
+        let vis = Visibility::from_syn(&item.vis);
+        let generics = Generics::from_syn(&item.generics);
+        let supertraits = item.supertraits.iter().map(bound_to_string).collect();
+        let items = item.items.iter().filter_map(AssocItem::from_syn).collect();
+        Self {
+            name,
+            vis,
+            generics,
+            supertraits,
+            is_unsafe: item.unsafety.is_some(),
+            is_auto: item.auto_token.is_some(),
+            items,
+            attrs: Attributes::from_syn(&item.attrs),
+            module,
+            span,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
+
+    pub fn supertraits(&self) -> &[String] {
+        &self.supertraits
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.is_unsafe
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.is_auto
+    }
+
+    pub fn items(&self) -> &[AssocItem] {
+        &self.items
+    }
+
+    pub fn required_items(&self) -> impl Iterator<Item = &AssocItem> {
+        self.items.iter().filter(|item| !item.has_default())
+    }
+
+    pub(crate) fn renamed(&self, new_name: &str) -> Self {
+        Self {
+            name: String::from(new_name),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn set_visibility(&mut self, vis: Visibility) {
+        self.vis = vis;
+    }
+}
+
+from_items!(traits_from_items, Trait, Trait);
@@ -4,14 +4,21 @@ use std::fmt::{self, Display, Formatter};
 
 use crate::{from_items, printer::TreePrintable, tree::TreeItem};
 
-use super::structs::{Path, Visibility};
+use super::{
+    attrs::Attributes,
+    generics::Generics,
+    span::Span,
+    structs::{Path, Visibility},
+};
 
 #[derive(Debug, Clone)]
 pub struct TypeAlias {
     name: String,
     vis: Visibility,
-    params: Vec<String>,
+    generics: Generics,
+    attrs: Attributes,
     module: Path,
+    span: Span,
 }
 
 impl Display for TypeAlias {
@@ -24,10 +31,7 @@ impl Display for TypeAlias {
             self.module,
             self.name.yellow(),
         )?;
-        if !self.params.is_empty() {
-            write!(f, "<{}>", self.params.join(","))?;
-        }
-        Ok(())
+        write!(f, "{}{}", self.generics, self.generics.where_clause())
     }
 }
 
@@ -52,21 +56,31 @@ impl TreePrintable for TypeAlias {
 }
 
 impl TypeAlias {
-    fn from_syn(item: &syn::ItemType, module: Path) -> Self {
+    fn from_syn(item: &syn::ItemType, module: Path, span: Span) -> Self {
         let name = item.ident.to_string();
         let vis = Visibility::from_syn(&item.vis);
-        let params: Vec<String> = item
-            .generics
-            .type_params()
-            .map(|param| param.ident.to_string())
-            .collect();
+        let generics = Generics::from_syn(&item.generics);
         Self {
             name,
             vis,
-            params,
+            generics,
+            attrs: Attributes::from_syn(&item.attrs),
             module,
+            span,
         }
     }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
 }
 
 from_items!(type_aliases_from_items, TypeAlias, Type);
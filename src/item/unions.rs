@@ -0,0 +1,93 @@
+use colored::*;
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{from_items, printer::TreePrintable, tree::TreeItem};
+
+use super::{
+    attrs::Attributes,
+    generics::Generics,
+    span::Span,
+    structs::{Fields, Path, Visibility},
+};
+
+#[derive(Debug, Clone)]
+pub struct Union {
+    name: String,
+    vis: Visibility,
+    generics: Generics,
+    fields: Fields,
+    attrs: Attributes,
+    module: Path,
+    span: Span,
+}
+
+impl Display for Union {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {}::{}",
+            self.vis.to_string().magenta(),
+            "union".green(),
+            self.module,
+            self.name.yellow(),
+        )?;
+        write!(f, "{}{}", self.generics, self.generics.where_clause())
+    }
+}
+
+impl TreeItem for Union {
+    fn module(&self) -> &Path {
+        &self.module
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl TreePrintable for Union {
+    fn single_write(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt(f)
+    }
+
+    fn children(&self) -> Vec<&dyn TreePrintable> {
+        self.fields.tree_children()
+    }
+}
+
+impl Union {
+    fn from_syn(item: &syn::ItemUnion, module: Path, span: Span) -> Self {
+        let name = item.ident.to_string();
+        let vis = Visibility::from_syn(&item.vis);
+        let generics = Generics::from_syn(&item.generics);
+        let fields = Fields::from_syn_named(&item.fields);
+        Self {
+            name,
+            vis,
+            generics,
+            fields,
+            attrs: Attributes::from_syn(&item.attrs),
+            module,
+            span,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn attrs(&self) -> &Attributes {
+        &self.attrs
+    }
+
+    pub fn fields(&self) -> &Fields {
+        &self.fields
+    }
+
+    pub fn generics(&self) -> &Generics {
+        &self.generics
+    }
+}
+
+from_items!(unions_from_items, Union, Union);
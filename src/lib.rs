@@ -1,15 +1,22 @@
 pub mod cargo;
-mod cfg;
+pub mod cfg;
 mod depgraph;
 pub mod error;
 pub mod explore;
 pub mod item;
+pub mod lockfile;
+pub mod options;
+pub mod patch;
 mod printer;
 mod stdlib;
+pub mod target;
 pub mod tree;
 mod use_path;
+pub mod workspace;
 
 #[macro_use]
 extern crate quick_error;
 
 pub use depgraph::DepGraph;
+pub use options::Options;
+pub use target::TargetSpec;
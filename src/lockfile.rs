@@ -0,0 +1,195 @@
+use std::{fs, path::Path as StdPath, str::FromStr};
+
+use cargo::core::{Dependency, PackageId, SourceId};
+use log::debug;
+use semver::Version;
+
+use crate::error::{Error, Result};
+
+// A package pinned by Cargo.lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    name: String,
+    version: Version,
+    // eg. `registry+https://github.com/rust-lang/crates.io-index` or
+    // `git+https://github.com/foo/bar?branch=main#<rev>`. Path
+    // dependencies have none.
+    source: Option<String>,
+}
+
+impl LockedPackage {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    fn matches(&self, dep: &Dependency) -> bool {
+        if self.name != dep.package_name().as_str() || !dep.version_req().matches(&self.version) {
+            return false;
+        }
+        let dep_src_id = dep.source_id();
+        match &self.source {
+            None => dep_src_id.is_path(),
+            Some(source) => match SourceId::from_url(source) {
+                Ok(src_id) => {
+                    src_id.is_git() == dep_src_id.is_git()
+                        && src_id.is_registry() == dep_src_id.is_registry()
+                        && src_id.url() == dep_src_id.url()
+                }
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    packages: Vec<LockedPackage>,
+}
+
+impl FromStr for Lockfile {
+    type Err = Error;
+
+    // Only the [[package]] tables are read, which look
+    // the same in all versions of the format
+    fn from_str(s: &str) -> Result<Self> {
+        let value: toml::Value = toml::from_str(s)?;
+        let invalid = |msg: &str| Error::InvalidLockfile(format!("Invalid Cargo.lock: {}", msg));
+        let mut packages = Vec::new();
+        let tables = match value.get("package") {
+            Some(toml::Value::Array(tables)) => tables.as_slice(),
+            Some(_) => return Err(invalid("package is not an array")),
+            None => &[],
+        };
+        for table in tables {
+            let name = table
+                .get("name")
+                .and_then(toml::Value::as_str)
+                .ok_or_else(|| invalid("package without a name"))?;
+            let version = table
+                .get("version")
+                .and_then(toml::Value::as_str)
+                .ok_or_else(|| invalid("package without a version"))?;
+            let source = table.get("source").and_then(toml::Value::as_str);
+            packages.push(LockedPackage {
+                name: String::from(name),
+                version: Version::parse(version)?,
+                source: source.map(String::from),
+            });
+        }
+        Ok(Self { packages })
+    }
+}
+
+impl Lockfile {
+    // The Cargo.lock next to the Cargo.toml in crate_root, if there is one
+    pub fn load<T: AsRef<StdPath>>(crate_root: T) -> Result<Option<Self>> {
+        let lock_path = crate_root.as_ref().join("Cargo.lock");
+        if !lock_path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&lock_path)?;
+        Ok(Some(content.parse()?))
+    }
+
+    pub fn packages(&self) -> &[LockedPackage] {
+        &self.packages
+    }
+
+    // The locked package dep resolves to. If several match, which only
+    // happens with a requirement spanning semver-incompatible versions,
+    // the latest is taken like when there is no lock.
+    pub fn locked(&self, dep: &Dependency) -> Option<&LockedPackage> {
+        self.packages
+            .iter()
+            .filter(|pkg| pkg.matches(dep))
+            .max_by_key(|pkg| &pkg.version)
+    }
+
+    // dep, narrowed down to exactly the locked version and,
+    // for git dependencies, the locked revision
+    pub(crate) fn pin(&self, dep: &Dependency) -> Result<Dependency> {
+        let mut dep = dep.clone();
+        match self.locked(&dep) {
+            Some(locked) => {
+                debug!("{} is locked to {}", dep.package_name(), locked.version);
+                if let Some(source) = &locked.source {
+                    if dep.source_id().is_git() {
+                        dep.set_source_id(SourceId::from_url(source)?);
+                    }
+                }
+                let id =
+                    PackageId::new(dep.package_name(), locked.version.clone(), dep.source_id())?;
+                dep.lock_to(id);
+            }
+            None => debug!("{} is not in Cargo.lock", dep.package_name()),
+        }
+        Ok(dep)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_lockfile() {
+        let lockfile: Lockfile = "version = 3\n\
+             [[package]]\n\
+             name = \"ratmole\"\n\
+             version = \"0.1.0\"\n\
+             dependencies = [\"syn\"]\n\
+             [[package]]\n\
+             name = \"syn\"\n\
+             version = \"1.0.80\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+             checksum = \"d010a1623fbd906d51d650a9916aaefc05ffa0e4053ff7fe601167f3e715d194\"\n"
+            .parse()
+            .unwrap();
+        let packages = lockfile.packages();
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name(), "ratmole");
+        assert_eq!(packages[0].source(), None);
+        assert_eq!(packages[1].version(), &Version::new(1, 0, 80));
+        assert!("[[package]]\nname = \"syn\"\n".parse::<Lockfile>().is_err());
+    }
+
+    #[test]
+    fn test_pin() {
+        let lockfile: Lockfile = "[[package]]\n\
+             name = \"syn\"\n\
+             version = \"1.0.80\"\n\
+             source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+             [[package]]\n\
+             name = \"foo\"\n\
+             version = \"0.2.0\"\n\
+             source = \"git+https://github.com/foo/foo?branch=main#0123456789abcdef\"\n"
+            .parse()
+            .unwrap();
+        let crates_io =
+            SourceId::from_url("registry+https://github.com/rust-lang/crates.io-index").unwrap();
+
+        let syn = Dependency::parse("syn", Some("1.0"), crates_io).unwrap();
+        let pinned = lockfile.pin(&syn).unwrap();
+        assert!(pinned.is_locked());
+        assert!(pinned.version_req().matches(&Version::new(1, 0, 80)));
+        assert!(!pinned.version_req().matches(&Version::new(1, 0, 81)));
+
+        let git = SourceId::from_url("git+https://github.com/foo/foo?branch=main").unwrap();
+        let foo = Dependency::parse("foo", None, git).unwrap();
+        let pinned = lockfile.pin(&foo).unwrap();
+        assert_eq!(pinned.source_id().precise(), Some("0123456789abcdef"));
+        assert!(pinned.version_req().matches(&Version::new(0, 2, 0)));
+
+        // Not in the lockfile
+        let log = Dependency::parse("log", Some("0.4"), crates_io).unwrap();
+        assert!(!lockfile.pin(&log).unwrap().is_locked());
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+use cargo::core::dependency::DepKind;
+
+use crate::{
+    cargo::{FeatureSelection, LocalSource},
+    target::TargetSpec,
+};
+
+// How a crate and its dependencies are to be analyzed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub(crate) targets: Vec<TargetSpec>,
+    pub(crate) features: FeatureSelection,
+    pub(crate) ignore_lockfile: bool,
+    pub(crate) offline: bool,
+    pub(crate) local_source: Option<LocalSource>,
+    pub(crate) members: Vec<String>,
+    pub(crate) dep_kinds: Vec<DepKind>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            targets: vec![TargetSpec::default()],
+            features: FeatureSelection::default(),
+            ignore_lockfile: false,
+            offline: false,
+            local_source: None,
+            members: Vec::new(),
+            dep_kinds: vec![DepKind::Normal, DepKind::Build],
+        }
+    }
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn target(self, target: TargetSpec) -> Self {
+        self.targets(vec![target])
+    }
+
+    pub fn targets(mut self, targets: Vec<TargetSpec>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    // Features of the root crate, or of the selected
+    // workspace members which have them
+    pub fn features(mut self, features: FeatureSelection) -> Self {
+        self.features = features;
+        self
+    }
+
+    // Resolve to the latest matching versions even if
+    // the root crate has a Cargo.lock
+    pub fn ignore_lockfile(mut self) -> Self {
+        self.ignore_lockfile = true;
+        self
+    }
+
+    // Use only what is already in the local cargo cache, without
+    // updating the index or fetching anything
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    // Take all dependencies which are not given by path from a
+    // `cargo vendor` directory. A [source] replacement in the
+    // crate's .cargo/config.toml is picked up without this.
+    pub fn vendor_dir<T: Into<PathBuf>>(mut self, path: T) -> Self {
+        self.local_source = Some(LocalSource::Vendor(path.into()));
+        self
+    }
+
+    // Like vendor_dir(), for a `cargo local-registry` directory
+    pub fn local_registry<T: Into<PathBuf>>(mut self, path: T) -> Self {
+        self.local_source = Some(LocalSource::Registry(path.into()));
+        self
+    }
+
+    // Only these packages of a workspace, by name, rather than all its
+    // members. Their dependencies are still taken from the workspace.
+    pub fn members<I, S>(mut self, members: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.members = members.into_iter().map(Into::into).collect();
+        self
+    }
+
+    // Which kinds of dependencies are in the graph, by default normal
+    // and build ones. Dev-dependencies are only those of the root crate
+    // or the workspace members, as Cargo never builds any others.
+    pub fn dep_kinds(mut self, kinds: Vec<DepKind>) -> Self {
+        self.dep_kinds = kinds;
+        self
+    }
+}
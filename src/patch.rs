@@ -0,0 +1,263 @@
+use std::{
+    fs,
+    path::{Path as StdPath, PathBuf},
+};
+
+use cargo::{
+    core::{Dependency, GitReference, PackageIdSpec, SourceId},
+    util::{CanonicalUrl, IntoUrl},
+    Config,
+};
+use log::debug;
+use toml::Value;
+
+use crate::error::{Error, Result};
+
+// The overrides in the [patch] and [replace] sections of the root
+// manifest, and in the [patch] sections of .cargo/config.toml files
+#[derive(Debug, Clone, Default)]
+pub struct Patches {
+    // URL of the source patched, and what to use instead
+    patches: Vec<(CanonicalUrl, Dependency)>,
+    replacements: Vec<(PackageIdSpec, Dependency)>,
+}
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidPatch(msg)
+}
+
+fn read_toml(path: &StdPath) -> Result<Option<Value>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    Ok(Some(toml::from_str(&content)?))
+}
+
+// The config files Cargo would read, nearest first,
+// along with the directory paths in them are relative to
+fn config_files(root: &StdPath, config: &Config) -> Vec<(PathBuf, PathBuf)> {
+    let mut dirs: Vec<_> = root.ancestors().map(|dir| dir.join(".cargo")).collect();
+    let home = config.home().as_path_unlocked().to_path_buf();
+    if !dirs.contains(&home) {
+        dirs.push(home);
+    }
+    let mut files = Vec::new();
+    for dir in dirs {
+        let base = dir.parent().unwrap_or(&dir).to_path_buf();
+        for name in &["config.toml", "config"] {
+            let file = dir.join(name);
+            if file.is_file() {
+                files.push((file, base));
+                break;
+            }
+        }
+    }
+    files
+}
+
+// An entry like `foo = "1.0"` or `foo = { path = "../foo" }`,
+// with paths relative to base
+fn parse_dependency(
+    name: &str,
+    value: &Value,
+    base: &StdPath,
+    config: &Config,
+) -> Result<Dependency> {
+    let table = match value {
+        Value::String(version) => {
+            return Ok(Dependency::parse(
+                name,
+                Some(version),
+                SourceId::crates_io(config)?,
+            )?)
+        }
+        Value::Table(table) => table,
+        _ => return Err(invalid(format!("Invalid entry for {}", name))),
+    };
+    let get = |key: &str| table.get(key).and_then(Value::as_str);
+    let source_id = if let Some(path) = get("path") {
+        SourceId::for_path(&base.join(path))?
+    } else if let Some(git) = get("git") {
+        let reference = match (get("branch"), get("tag"), get("rev")) {
+            (Some(branch), _, _) => GitReference::Branch(String::from(branch)),
+            (_, Some(tag), _) => GitReference::Tag(String::from(tag)),
+            (_, _, Some(rev)) => GitReference::Rev(String::from(rev)),
+            _ => GitReference::DefaultBranch,
+        };
+        SourceId::for_git(&git.into_url()?, reference)?
+    } else {
+        SourceId::crates_io(config)?
+    };
+    let package = get("package").unwrap_or(name);
+    Ok(Dependency::parse(package, get("version"), source_id)?)
+}
+
+impl Patches {
+    // From the manifest in root, which is the workspace root if
+    // there is one, and the config files which apply to it
+    pub fn load<T: AsRef<StdPath>>(root: T, config: &Config) -> Result<Self> {
+        let root = root.as_ref();
+        let mut patches = Self::default();
+        // Config takes precedence over the manifest
+        for (file, base) in config_files(root, config) {
+            if let Some(value) = read_toml(&file)? {
+                patches.add_patches(&value, &base, config)?;
+            }
+        }
+        if let Some(value) = read_toml(&root.join("Cargo.toml"))? {
+            patches.add_patches(&value, root, config)?;
+            patches.add_replacements(&value, root, config)?;
+        }
+        debug!(
+            "{} patches, {} replacements",
+            patches.patches.len(),
+            patches.replacements.len()
+        );
+        Ok(patches)
+    }
+
+    fn add_patches(&mut self, value: &Value, base: &StdPath, config: &Config) -> Result<()> {
+        let sources = match value.get("patch").and_then(Value::as_table) {
+            Some(sources) => sources,
+            None => return Ok(()),
+        };
+        for (source, deps) in sources {
+            // Canonical, so that eg. a trailing slash or .git
+            // in the URL of a git repository doesn't matter
+            let url = if source == "crates-io" {
+                SourceId::crates_io(config)?.canonical_url().clone()
+            } else {
+                CanonicalUrl::new(&source.as_str().into_url()?)?
+            };
+            let deps = deps
+                .as_table()
+                .ok_or_else(|| invalid(format!("Invalid [patch.{}]", source)))?;
+            for (name, dep) in deps {
+                let dep = parse_dependency(name, dep, base, config)?;
+                self.patches.push((url.clone(), dep));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_replacements(&mut self, value: &Value, base: &StdPath, config: &Config) -> Result<()> {
+        let replacements = match value.get("replace").and_then(Value::as_table) {
+            Some(replacements) => replacements,
+            None => return Ok(()),
+        };
+        for (spec, dep) in replacements {
+            let spec = PackageIdSpec::parse(spec)?;
+            let dep = parse_dependency(&spec.name(), dep, base, config)?;
+            self.replacements.push((spec, dep));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty() && self.replacements.is_empty()
+    }
+
+    // What dep could be taken from instead, in order. Patches are only
+    // used if the version they have satisfies dep, which is up to the
+    // caller to check; a [replace] applies regardless.
+    pub(crate) fn candidates(&self, dep: &Dependency) -> Vec<(&Dependency, bool)> {
+        let source_id = dep.source_id();
+        let source_url = source_id.canonical_url();
+        let replacements = self.replacements.iter().filter(|(spec, _)| {
+            spec.name() == dep.package_name()
+                && spec
+                    .version()
+                    .map_or(true, |version| dep.version_req().matches(version))
+        });
+        let patches = self
+            .patches
+            .iter()
+            .filter(|(url, patch)| url == source_url && patch.package_name() == dep.package_name());
+        replacements
+            .map(|(_, replacement)| (replacement, false))
+            .chain(patches.map(|(_, patch)| (patch, true)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cargo::cargo_config;
+    use semver::Version;
+
+    #[test]
+    fn test_parse_dependency() {
+        let base = std::env::temp_dir();
+        let config = cargo_config(&base, true).unwrap();
+        let dep = parse_dependency("log", &Value::from("0.4"), &base, &config).unwrap();
+        assert_eq!(dep.source_id(), SourceId::crates_io(&config).unwrap());
+        assert!(dep
+            .version_req()
+            .matches(&Version::parse("0.4.14").unwrap()));
+
+        let value = toml::from_str("package = \"bar-rs\"\npath = \"bar\"").unwrap();
+        let dep = parse_dependency("bar", &value, &base, &config).unwrap();
+        assert_eq!(dep.package_name(), "bar-rs");
+        assert_eq!(
+            dep.source_id(),
+            SourceId::for_path(&base.join("bar")).unwrap()
+        );
+
+        let value = toml::from_str("git = \"https://github.com/foo/foo\"\ntag = \"v1\"").unwrap();
+        let dep = parse_dependency("foo", &value, &base, &config).unwrap();
+        assert!(dep.source_id().is_git());
+        assert_eq!(
+            dep.source_id().git_reference(),
+            Some(&GitReference::Tag(String::from("v1")))
+        );
+
+        assert!(parse_dependency("foo", &Value::from(1), &base, &config).is_err());
+    }
+
+    #[test]
+    fn test_candidates() {
+        let base = std::env::temp_dir();
+        let config = cargo_config(&base, true).unwrap();
+        let manifest: Value = toml::from_str(
+            "[patch.crates-io]\n\
+             log = { path = \"log\" }\n\
+             [patch.'https://github.com/foo/foo.git']\n\
+             foo = { path = \"foo\" }\n\
+             [replace]\n\
+             \"serde:1.0.100\" = { path = \"serde\" }\n",
+        )
+        .unwrap();
+        let mut patches = Patches::default();
+        patches.add_patches(&manifest, &base, &config).unwrap();
+        patches.add_replacements(&manifest, &base, &config).unwrap();
+        let crates_io = SourceId::crates_io(&config).unwrap();
+
+        let log = Dependency::parse("log", Some("0.4"), crates_io).unwrap();
+        let candidates = patches.candidates(&log);
+        assert_eq!(candidates.len(), 1);
+        let (patch, check_version) = candidates[0];
+        assert_eq!(
+            patch.source_id(),
+            SourceId::for_path(&base.join("log")).unwrap()
+        );
+        assert!(check_version);
+
+        // The same repository, without the .git
+        let url = "https://github.com/foo/foo".into_url().unwrap();
+        let git = SourceId::for_git(&url, GitReference::DefaultBranch).unwrap();
+        let foo = Dependency::parse("foo", None, git).unwrap();
+        assert_eq!(patches.candidates(&foo).len(), 1);
+        // Only the source patched is
+        let foo = Dependency::parse("foo", None, crates_io).unwrap();
+        assert!(patches.candidates(&foo).is_empty());
+
+        let serde = Dependency::parse("serde", Some("1.0"), crates_io).unwrap();
+        let candidates = patches.candidates(&serde);
+        assert_eq!(candidates.len(), 1);
+        assert!(!candidates[0].1);
+        let serde = Dependency::parse("serde", Some("0.9"), crates_io).unwrap();
+        assert!(patches.candidates(&serde).is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path as StdPath,
+};
+
+use crate::{
+    cfg::{Cfg, CfgSet, Platform},
+    error::Result,
+};
+
+// The platform crates are analyzed for: dependencies are filtered
+// by it and #[cfg]s are evaluated against its cfgs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetSpec {
+    triple: String,
+    cfgs: CfgSet,
+}
+
+impl Display for TargetSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.triple)
+    }
+}
+
+impl Default for TargetSpec {
+    fn default() -> Self {
+        Self::builtin("x86_64-unknown-linux-gnu").unwrap()
+    }
+}
+
+struct BuiltinTarget {
+    triple: &'static str,
+    arch: &'static str,
+    os: &'static str,
+    families: &'static [&'static str],
+    env: &'static str,
+    vendor: &'static str,
+    pointer_width: &'static str,
+    features: &'static [&'static str],
+    panic: &'static str,
+}
+
+const X86_FEATURES: &[&str] = &["fxsr", "sse", "sse2"];
+const MACOS_X86_FEATURES: &[&str] = &["fxsr", "sse", "sse2", "sse3", "ssse3"];
+const AARCH64_FEATURES: &[&str] = &["neon"];
+const ARMV7_FEATURES: &[&str] = &[
+    "dsp", "neon", "thumb2", "v5te", "v6", "v6k", "v6t2", "v7", "vfp2", "vfp3",
+];
+
+// Tier 1 and the more common tier 2 targets
+const BUILTIN_TARGETS: &[BuiltinTarget] = &[
+    BuiltinTarget {
+        triple: "x86_64-unknown-linux-gnu",
+        arch: "x86_64",
+        os: "linux",
+        families: &["unix"],
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "64",
+        features: X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "x86_64-unknown-linux-musl",
+        arch: "x86_64",
+        os: "linux",
+        families: &["unix"],
+        env: "musl",
+        vendor: "unknown",
+        pointer_width: "64",
+        features: X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "i686-unknown-linux-gnu",
+        arch: "x86",
+        os: "linux",
+        families: &["unix"],
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "32",
+        features: X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "aarch64-unknown-linux-gnu",
+        arch: "aarch64",
+        os: "linux",
+        families: &["unix"],
+        env: "gnu",
+        vendor: "unknown",
+        pointer_width: "64",
+        features: AARCH64_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "aarch64-unknown-linux-musl",
+        arch: "aarch64",
+        os: "linux",
+        families: &["unix"],
+        env: "musl",
+        vendor: "unknown",
+        pointer_width: "64",
+        features: AARCH64_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "x86_64-pc-windows-msvc",
+        arch: "x86_64",
+        os: "windows",
+        families: &["windows"],
+        env: "msvc",
+        vendor: "pc",
+        pointer_width: "64",
+        features: X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "x86_64-pc-windows-gnu",
+        arch: "x86_64",
+        os: "windows",
+        families: &["windows"],
+        env: "gnu",
+        vendor: "pc",
+        pointer_width: "64",
+        features: X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "i686-pc-windows-msvc",
+        arch: "x86",
+        os: "windows",
+        families: &["windows"],
+        env: "msvc",
+        vendor: "pc",
+        pointer_width: "32",
+        features: X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "x86_64-apple-darwin",
+        arch: "x86_64",
+        os: "macos",
+        families: &["unix"],
+        env: "",
+        vendor: "apple",
+        pointer_width: "64",
+        features: MACOS_X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "aarch64-apple-darwin",
+        arch: "aarch64",
+        os: "macos",
+        families: &["unix"],
+        env: "",
+        vendor: "apple",
+        pointer_width: "64",
+        features: AARCH64_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "aarch64-apple-ios",
+        arch: "aarch64",
+        os: "ios",
+        families: &["unix"],
+        env: "",
+        vendor: "apple",
+        pointer_width: "64",
+        features: AARCH64_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "aarch64-linux-android",
+        arch: "aarch64",
+        os: "android",
+        families: &["unix"],
+        env: "",
+        vendor: "unknown",
+        pointer_width: "64",
+        features: AARCH64_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "armv7-linux-androideabi",
+        arch: "arm",
+        os: "android",
+        families: &["unix"],
+        env: "",
+        vendor: "unknown",
+        pointer_width: "32",
+        features: ARMV7_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "x86_64-unknown-freebsd",
+        arch: "x86_64",
+        os: "freebsd",
+        families: &["unix"],
+        env: "",
+        vendor: "unknown",
+        pointer_width: "64",
+        features: X86_FEATURES,
+        panic: "unwind",
+    },
+    BuiltinTarget {
+        triple: "wasm32-unknown-unknown",
+        arch: "wasm32",
+        os: "unknown",
+        families: &["wasm"],
+        env: "",
+        vendor: "unknown",
+        pointer_width: "32",
+        features: &[],
+        panic: "abort",
+    },
+    BuiltinTarget {
+        triple: "wasm32-wasi",
+        arch: "wasm32",
+        os: "wasi",
+        families: &["wasm"],
+        env: "",
+        vendor: "unknown",
+        pointer_width: "32",
+        features: &[],
+        panic: "abort",
+    },
+];
+
+impl BuiltinTarget {
+    fn cfgs(&self) -> CfgSet {
+        let mut cfgs = vec![Cfg::from(("target_arch", self.arch))];
+        cfgs.extend(
+            self.features
+                .iter()
+                .map(|feature| Cfg::from(("target_feature", *feature))),
+        );
+        cfgs.push(Cfg::from(("target_os", self.os)));
+        for family in self.families {
+            cfgs.push(Cfg::from(("target_family", *family)));
+            // Only these two are also set as bare names
+            if *family == "unix" || *family == "windows" {
+                cfgs.push(Cfg::from(*family));
+            }
+        }
+        cfgs.push(Cfg::from(("target_env", self.env)));
+        cfgs.push(Cfg::from(("target_endian", "little")));
+        cfgs.push(Cfg::from(("target_pointer_width", self.pointer_width)));
+        cfgs.push(Cfg::from(("target_vendor", self.vendor)));
+        for width in &["8", "16", "32", "64", "ptr"] {
+            cfgs.push(Cfg::from(("target_has_atomic", *width)));
+        }
+        cfgs.push(Cfg::from(("panic", self.panic)));
+        cfgs.into_iter().collect()
+    }
+}
+
+impl TargetSpec {
+    pub fn new<T: Into<String>>(triple: T, cfgs: CfgSet) -> Self {
+        Self {
+            triple: triple.into(),
+            cfgs,
+        }
+    }
+
+    // One of the targets listed by builtin_triples()
+    pub fn builtin(triple: &str) -> Option<Self> {
+        BUILTIN_TARGETS
+            .iter()
+            .find(|target| target.triple == triple)
+            .map(|target| Self::new(triple, target.cfgs()))
+    }
+
+    pub fn builtin_triples() -> impl Iterator<Item = &'static str> {
+        BUILTIN_TARGETS.iter().map(|target| target.triple)
+    }
+
+    // The output of `rustc --print cfg --target <triple>`,
+    // which does not mention the triple itself
+    pub fn from_rustc_cfg<T: Into<String>>(triple: T, output: &str) -> Result<Self> {
+        // Each line is an option, eg. `unix` or `target_os="linux"`
+        let cfgs = output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::parse)
+            .collect::<Result<CfgSet>>()?;
+        Ok(Self::new(triple, cfgs))
+    }
+
+    pub fn from_rustc_cfg_file<T, P>(triple: T, path: P) -> Result<Self>
+    where
+        T: Into<String>,
+        P: AsRef<StdPath>,
+    {
+        let output = fs::read_to_string(path)?;
+        Self::from_rustc_cfg(triple, &output)
+    }
+
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    pub fn cfgs(&self) -> &CfgSet {
+        &self.cfgs
+    }
+
+    // Whether a `[target.'...'.dependencies]` table applies
+    pub(crate) fn matches_platform(&self, platform: &Platform) -> bool {
+        match platform {
+            Platform::Name(name) => name == &self.triple,
+            Platform::CfgExpr(cfg_expr) => cfg_expr.eval(&self.cfgs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rustc_cfg() {
+        let output = "debug_assertions\n\
+                      panic=\"unwind\"\n\
+                      target_arch=\"aarch64\"\n\
+                      target_os=\"android\"\n\
+                      target_family=\"unix\"\n\
+                      unix\n";
+        let target = TargetSpec::from_rustc_cfg("aarch64-linux-android", output).unwrap();
+        assert_eq!(target.triple(), "aarch64-linux-android");
+        assert_eq!(target.cfgs().len(), 6);
+        assert!(target.cfgs().contains(&Cfg::from(("target_os", "android"))));
+        assert!(target.cfgs().contains(&Cfg::from("unix")));
+        assert!(TargetSpec::from_rustc_cfg("x", "target_os=android").is_err());
+
+        let builtin = TargetSpec::builtin("aarch64-linux-android").unwrap();
+        for cfg in target
+            .cfgs()
+            .iter()
+            .filter(|cfg| **cfg != Cfg::from("debug_assertions"))
+        {
+            assert!(builtin.cfgs().contains(cfg), "{:?} missing", cfg);
+        }
+        assert!(TargetSpec::builtin_triples().any(|triple| triple == "wasm32-unknown-unknown"));
+    }
+}
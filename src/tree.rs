@@ -77,8 +77,13 @@ where
     }
 
     pub(crate) fn add_item(&mut self, t: &'t T) {
-        let comps: Vec<&str> = t
-            .module()
+        self.add_item_at(t.module(), t.name(), t);
+    }
+
+    // Adds t to the tree as if it were defined in module under name,
+    // which is what a re-export does.
+    pub(crate) fn add_item_at(&mut self, module: &Path, name: &str, t: &'t T) {
+        let comps: Vec<&str> = module
             .components()
             .iter()
             .map(|comp| {
@@ -92,7 +97,7 @@ where
                 }
             })
             .collect();
-        node_add_item(&mut self.root, &comps, t);
+        node_add_item(&mut self.root, &comps, name, t);
     }
 
     pub fn resolve_use_path<'item>(
@@ -111,19 +116,16 @@ where
     }
 }
 
-fn node_add_item<'t, 'c, T>(node: &mut PathNode<'t, T>, comps: &'c [&'t str], item: &'t T)
-where
-    T: TreeItem,
-{
+fn node_add_item<'t, T>(node: &mut PathNode<'t, T>, comps: &[&str], name: &str, item: &'t T) {
     if comps.is_empty() {
-        node.child_items.insert(String::from(item.name()), item);
+        node.child_items.insert(String::from(name), item);
     } else {
         if !node.child_mods.contains_key(comps[0]) {
             let name = String::from(comps[0]);
             node.child_mods.insert(name.clone(), PathNode::new(name));
         }
         let new_node = node.child_mods.get_mut(comps[0]).unwrap();
-        node_add_item(new_node, &comps[1..], item);
+        node_add_item(new_node, &comps[1..], name, item);
     }
 }
 
@@ -154,4 +156,10 @@ where
 pub trait TreeItem {
     fn name(&self) -> &str;
     fn module(&self) -> &Path;
+
+    fn path(&self) -> Path {
+        let mut path = self.module().clone();
+        path.push_name(String::from(self.name()));
+        path
+    }
 }
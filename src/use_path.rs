@@ -4,9 +4,13 @@ use regex::Regex;
 use std::{
     collections::HashMap,
     fmt::{self, Display, Formatter},
+    path::Path as StdPath,
 };
 
-use crate::item::structs::{Path, Visibility};
+use crate::item::{
+    span::Span,
+    structs::{Path, Visibility},
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UsePathComponent {
@@ -41,11 +45,17 @@ impl Display for UsePathComponent {
 pub struct UsePath {
     path: Vec<UsePathComponent>,
     vis: Visibility,
+    // Only use paths parsed from a `use` item have a span
+    span: Option<Span>,
 }
 
 impl UsePath {
     fn new(path: Vec<UsePathComponent>, vis: Visibility) -> Self {
-        Self { path, vis }
+        Self {
+            path,
+            vis,
+            span: None,
+        }
     }
 
     pub fn components(&self) -> &[UsePathComponent] {
@@ -56,6 +66,10 @@ impl UsePath {
         &self.vis
     }
 
+    pub fn span(&self) -> Option<&Span> {
+        self.span.as_ref()
+    }
+
     // Given a use_path self belonging to module,
     // this method scans self for special path components
     // crate, self and super. (These are special because they
@@ -112,6 +126,41 @@ impl UsePath {
     pub fn remove_first(&mut self) {
         self.path.remove(0);
     }
+
+    // The name this use path brings into scope, if it is not a glob
+    pub fn imported_name(&self) -> Option<&str> {
+        match self.path.last() {
+            Some(UsePathComponent::Name(name)) => Some(name),
+            Some(UsePathComponent::Rename(_, rename)) => Some(rename),
+            _ => None,
+        }
+    }
+
+    // Replaces the first component of self with the full path
+    // of the import which brought it into scope.
+    // So `Foo::Bar` with `use a::b::Foo` becomes `a::b::Foo::Bar`.
+    pub fn expand_import(&self, import: &UsePath) -> Self {
+        let mut path = import.path.clone();
+        if let Some(UsePathComponent::Rename(name, _)) = path.last() {
+            let name = name.clone();
+            *path.last_mut().unwrap() = UsePathComponent::Name(name);
+        }
+        path.extend(self.path.iter().skip(1).cloned());
+        Self::new(path, self.vis.clone())
+    }
+
+    pub(crate) fn from_syn_path(path: &syn::Path) -> Self {
+        let mut comps = Vec::new();
+        if path.leading_colon.is_some() {
+            comps.push(UsePathComponent::Empty);
+        }
+        comps.extend(
+            path.segments
+                .iter()
+                .map(|seg| UsePathComponent::Name(seg.ident.to_string())),
+        );
+        Self::new(comps, Visibility::Private)
+    }
 }
 
 impl Display for UsePath {
@@ -151,6 +200,7 @@ impl From<Vec<&str>> for UsePath {
         Self {
             path: comps,
             vis: Visibility::Public,
+            span: None,
         }
     }
 }
@@ -197,13 +247,21 @@ fn use_paths_from_use_tree(tree: &syn::UseTree, vis: &Visibility) -> Vec<UsePath
     }
 }
 
-pub fn use_paths_from_items(items: &[syn::Item], module: &mut Path) -> HashMap<Path, Vec<UsePath>> {
+pub fn use_paths_from_items(
+    items: &[syn::Item],
+    module: &mut Path,
+    file: &StdPath,
+) -> HashMap<Path, Vec<UsePath>> {
     let mut paths_map: HashMap<Path, Vec<UsePath>> = HashMap::new();
     for item in items {
         match item {
             syn::Item::Use(item) => {
                 let mut new_paths =
                     use_paths_from_use_tree(&item.tree, &Visibility::from_syn(&item.vis));
+                let span = Span::from_syn(item, file);
+                for path in &mut new_paths {
+                    path.span = Some(span.clone());
+                }
                 if let Some(existing_paths) = paths_map.get_mut(module) {
                     existing_paths.append(&mut new_paths);
                 } else {
@@ -213,7 +271,7 @@ pub fn use_paths_from_items(items: &[syn::Item], module: &mut Path) -> HashMap<P
             syn::Item::Mod(item) => {
                 if let Some((_, items)) = item.content.as_ref() {
                     module.push_name(item.ident.to_string());
-                    let new_paths = use_paths_from_items(items, module);
+                    let new_paths = use_paths_from_items(items, module, file);
                     for (k, v) in new_paths {
                         paths_map.insert(k, v);
                     }
@@ -259,4 +317,23 @@ mod test {
         assert_eq!(path.delocalize(&module), new_mod);
         assert_eq!(path, new_path);
     }
+
+    #[test]
+    fn test_expand_import() {
+        let import = UsePath::from(vec!["super", "rngs", "StdRng"]);
+        let path = UsePath::from(vec!["StdRng"]);
+        assert_eq!(import.imported_name(), Some("StdRng"));
+        assert_eq!(
+            path.expand_import(&import),
+            UsePath::from(vec!["super", "rngs", "StdRng"])
+        );
+
+        let import = UsePath::from(vec!["crate", "distributions", "uniform as uni"]);
+        let path = UsePath::from(vec!["uni", "Uniform"]);
+        assert_eq!(import.imported_name(), Some("uni"));
+        assert_eq!(
+            path.expand_import(&import),
+            UsePath::from(vec!["crate", "distributions", "uniform", "Uniform"])
+        );
+    }
 }
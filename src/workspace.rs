@@ -0,0 +1,352 @@
+use std::{
+    fs,
+    path::{Path as StdPath, PathBuf},
+};
+
+use log::debug;
+use regex::Regex;
+use toml::{value::Table, Value};
+
+use crate::{
+    cargo::Resolver,
+    error::{Error, Result},
+};
+
+// A Cargo workspace: the directory whose Cargo.toml has a [workspace]
+// table, and the packages it contains
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    root: PathBuf,
+    members: Vec<PathBuf>,
+    manifest: Value,
+}
+
+// Package fields paths in which are relative to the workspace root
+const PATH_FIELDS: &[&str] = &["license-file", "readme"];
+
+const DEPENDENCY_TABLES: &[&str] = &[
+    "dependencies",
+    "dev-dependencies",
+    "dev_dependencies",
+    "build-dependencies",
+    "build_dependencies",
+];
+
+fn read_manifest(path: &StdPath) -> Result<Value> {
+    let content = fs::read_to_string(path.join("Cargo.toml"))?;
+    Ok(toml::from_str(&content)?)
+}
+
+fn invalid(root: &StdPath, msg: String) -> Error {
+    Error::InvalidWorkspace(format!("Invalid workspace {}: {}", root.display(), msg))
+}
+
+fn has_glob(component: &str) -> bool {
+    component.contains(&['*', '?'][..])
+}
+
+// Only `*` and `?` are supported, and not across directories
+fn glob_regex(component: &str) -> Regex {
+    let mut pattern = String::from("^");
+    for c in component.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).unwrap()
+}
+
+// The directories matching a `members` entry like `crates/*`
+fn expand_glob(root: &StdPath, pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![root.to_path_buf()];
+    for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !has_glob(component) {
+            paths = paths.into_iter().map(|path| path.join(component)).collect();
+            continue;
+        }
+        let regex = glob_regex(component);
+        let mut matched = Vec::new();
+        for path in paths.iter().filter(|path| path.is_dir()) {
+            for entry in fs::read_dir(path)? {
+                let entry = entry?;
+                let is_match = entry
+                    .file_name()
+                    .to_str()
+                    .map_or(false, |name| !name.starts_with('.') && regex.is_match(name));
+                if is_match && entry.path().is_dir() {
+                    matched.push(entry.path());
+                }
+            }
+        }
+        matched.sort();
+        paths = matched;
+    }
+    Ok(paths)
+}
+
+fn string_array<'a>(table: &'a Value, key: &str) -> Vec<&'a str> {
+    table
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|values| values.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+// `field.workspace = true`
+fn is_inherited(value: &Value) -> bool {
+    value.get("workspace").and_then(Value::as_bool) == Some(true)
+}
+
+fn dependency_tables_mut(manifest: &mut Value) -> Vec<&mut Table> {
+    let manifest = match manifest.as_table_mut() {
+        Some(manifest) => manifest,
+        None => return Vec::new(),
+    };
+    let mut tables = Vec::new();
+    for (key, value) in manifest.iter_mut() {
+        if DEPENDENCY_TABLES.contains(&key.as_str()) {
+            tables.extend(value.as_table_mut());
+        } else if key == "target" {
+            let platforms = value
+                .as_table_mut()
+                .into_iter()
+                .flat_map(|t| t.iter_mut().map(|(_, v)| v));
+            for platform in platforms.filter_map(Value::as_table_mut) {
+                for (key, value) in platform.iter_mut() {
+                    if DEPENDENCY_TABLES.contains(&key.as_str()) {
+                        tables.extend(value.as_table_mut());
+                    }
+                }
+            }
+        }
+    }
+    tables
+}
+
+impl Workspace {
+    // The workspace rooted at path, if its Cargo.toml has a [workspace]
+    pub fn load<T: AsRef<StdPath>>(path: T) -> Result<Option<Self>> {
+        let root = fs::canonicalize(path.as_ref())?;
+        let manifest = read_manifest(&root)?;
+        if manifest.get("workspace").is_none() {
+            return Ok(None);
+        }
+        let workspace = &manifest["workspace"];
+        let excluded: Vec<_> = string_array(workspace, "exclude")
+            .into_iter()
+            .map(|path| root.join(path))
+            .collect();
+        // A root package is always a member
+        let mut members = Vec::new();
+        if manifest.get("package").is_some() {
+            members.push(root.clone());
+        }
+        for pattern in string_array(workspace, "members") {
+            for path in expand_glob(&root, pattern)? {
+                if !path.join("Cargo.toml").exists() {
+                    // Globs may match directories which are not packages
+                    if has_glob(pattern) {
+                        continue;
+                    }
+                    return Err(invalid(
+                        &root,
+                        format!("member {} has no Cargo.toml", path.display()),
+                    ));
+                }
+                let path = fs::canonicalize(path)?;
+                let is_excluded = excluded.iter().any(|excluded| path.starts_with(excluded));
+                if !is_excluded && !members.contains(&path) {
+                    members.push(path);
+                }
+            }
+        }
+        debug!("Workspace {} has members {:?}", root.display(), members);
+        Ok(Some(Self {
+            root,
+            members,
+            manifest,
+        }))
+    }
+
+    // The workspace crate_root is a member of, found by
+    // looking upwards from it like Cargo does
+    pub fn find<T: AsRef<StdPath>>(crate_root: T) -> Result<Option<Self>> {
+        let crate_root = fs::canonicalize(crate_root.as_ref())?;
+        for dir in crate_root.ancestors() {
+            if !dir.join("Cargo.toml").exists() {
+                continue;
+            }
+            if let Some(workspace) = Self::load(dir)? {
+                if workspace.members.contains(&crate_root) {
+                    return Ok(Some(workspace));
+                }
+                return Ok(None);
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn root(&self) -> &StdPath {
+        &self.root
+    }
+
+    // The root package first, if there is one, then the
+    // others in the order of `members`
+    pub fn members(&self) -> &[PathBuf] {
+        &self.members
+    }
+
+    // Set with `resolver` in [workspace], or else like for
+    // the root package if there is one
+    pub fn resolver(&self) -> Resolver {
+        let package = self.manifest.get("package");
+        let resolver = self.manifest["workspace"]
+            .get("resolver")
+            .or_else(|| package.and_then(|package| package.get("resolver")))
+            .and_then(Value::as_str);
+        let edition = package
+            .and_then(|package| package.get("edition"))
+            .and_then(Value::as_str);
+        match (resolver, edition) {
+            (Some("2"), _) | (None, Some("2021")) => Resolver::V2,
+            _ => Resolver::V1,
+        }
+    }
+
+    fn package_field(&self, key: &str) -> Result<Value> {
+        let value = self.manifest["workspace"]
+            .get("package")
+            .and_then(|package| package.get(key))
+            .ok_or_else(|| invalid(&self.root, format!("{} is not in [workspace.package]", key)))?;
+        match value.as_str() {
+            Some(path) if PATH_FIELDS.contains(&key) => {
+                Ok(Value::from(self.root.join(path).to_string_lossy().as_ref()))
+            }
+            _ => Ok(value.clone()),
+        }
+    }
+
+    // The entry from [workspace.dependencies], with the
+    // features and optional-ness of the member's entry
+    fn dependency(&self, name: &str, member_dep: &Value) -> Result<Value> {
+        let value = self.manifest["workspace"]
+            .get("dependencies")
+            .and_then(|deps| deps.get(name))
+            .ok_or_else(|| {
+                invalid(
+                    &self.root,
+                    format!("{} is not in [workspace.dependencies]", name),
+                )
+            })?;
+        let mut dep = match value {
+            Value::String(version) => {
+                let mut dep = Table::new();
+                dep.insert(String::from("version"), Value::from(version.as_str()));
+                dep
+            }
+            Value::Table(dep) => dep.clone(),
+            _ => {
+                return Err(invalid(
+                    &self.root,
+                    format!("dependency {} is invalid", name),
+                ))
+            }
+        };
+        if let Some(path) = dep.get("path").and_then(Value::as_str) {
+            let path = self.root.join(path);
+            dep.insert(
+                String::from("path"),
+                Value::from(path.to_string_lossy().as_ref()),
+            );
+        }
+        let member_dep = member_dep.as_table().into_iter().flatten();
+        for (key, value) in member_dep.filter(|(key, _)| *key != "workspace") {
+            match (key.as_str(), dep.get_mut(key)) {
+                ("features", Some(Value::Array(features))) => {
+                    features.extend(value.as_array().into_iter().flatten().cloned());
+                }
+                _ => {
+                    dep.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        Ok(Value::Table(dep))
+    }
+
+    // Replaces the fields of a member's manifest which
+    // are inherited from the workspace by their values
+    pub(crate) fn inherit(&self, manifest: &mut Value) -> Result<()> {
+        if let Some(package) = manifest.get_mut("package").and_then(Value::as_table_mut) {
+            for (key, value) in package.iter_mut() {
+                if is_inherited(value) {
+                    *value = self.package_field(key)?;
+                }
+            }
+        }
+        for deps in dependency_tables_mut(manifest) {
+            for (name, dep) in deps.iter_mut() {
+                if is_inherited(dep) {
+                    *dep = self.dependency(name, dep)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_inherit() {
+        let workspace = Workspace {
+            root: PathBuf::from("/ws"),
+            members: vec![PathBuf::from("/ws/crates/a")],
+            manifest: toml::from_str(
+                "[workspace]\n\
+                 members = [\"crates/*\"]\n\
+                 [workspace.package]\n\
+                 version = \"0.2.0\"\n\
+                 readme = \"README.md\"\n\
+                 [workspace.dependencies]\n\
+                 log = \"0.4\"\n\
+                 syn = { version = \"1.0\", features = [\"full\"] }\n\
+                 b = { path = \"crates/b\" }\n",
+            )
+            .unwrap(),
+        };
+        let mut manifest: Value = toml::from_str(
+            "[package]\n\
+             name = \"a\"\n\
+             version.workspace = true\n\
+             readme = { workspace = true }\n\
+             [dependencies]\n\
+             log = { workspace = true, optional = true }\n\
+             syn = { workspace = true, features = [\"visit\"] }\n\
+             [target.'cfg(unix)'.dev-dependencies]\n\
+             b.workspace = true\n",
+        )
+        .unwrap();
+        workspace.inherit(&mut manifest).unwrap();
+        assert_eq!(manifest["package"]["version"].as_str(), Some("0.2.0"));
+        assert_eq!(
+            manifest["package"]["readme"].as_str(),
+            Some("/ws/README.md")
+        );
+        let deps = &manifest["dependencies"];
+        assert_eq!(deps["log"]["version"].as_str(), Some("0.4"));
+        assert_eq!(deps["log"]["optional"].as_bool(), Some(true));
+        assert_eq!(deps["syn"]["features"].as_array().unwrap().len(), 2);
+        let b = &manifest["target"]["cfg(unix)"]["dev-dependencies"]["b"];
+        assert_eq!(b["path"].as_str(), Some("/ws/crates/b"));
+
+        let mut manifest: Value = toml::from_str("[dependencies]\nc.workspace = true\n").unwrap();
+        assert!(workspace.inherit(&mut manifest).is_err());
+        assert!(glob_regex("foo-*").is_match("foo-bar"));
+        assert!(!glob_regex("foo-?").is_match("foo-bar"));
+    }
+}